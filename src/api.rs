@@ -1,12 +1,26 @@
 use crate::{
-    cfg::ConfigManager,
-    utils::{clear_screen, cursor, parse_duration, read},
+    cfg::{ConfigManager, DirectoryConfig},
+    journal::{self, Operation},
+    prompt::Prompter,
+    utils::{
+        display_duration, interactive, parse_date, parse_end_span, parse_time, require_input,
+        subtract, to_local, Interval,
+    },
 };
-use chrono::NaiveTime;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, SecondsFormat, Utc};
 use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+/// How many recently used tags are offered first when selecting tags
+const RECENT_TAGS: usize = 5;
+const RECENT_OPTION: &str = "* All recent tags";
+const CREATE_OPTION: &str = "+ Create a new tag";
+
 pub struct ClockifyCLI {
     pub api: Clockify,
+    pub prompter: Box<dyn Prompter>,
+    /// Whether tasks overlapping the logged ones are created without asking
+    pub allow_overlap: bool,
 }
 
 pub struct Clockify {
@@ -17,6 +31,13 @@ pub struct User {
     pub id: String,
     pub email: String,
     pub name: String,
+    pub settings: Option<UserSettings>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct UserSettings {
+    #[serde(rename = "timeZone")]
+    pub time_zone: Option<String>,
 }
 
 #[derive(Deserialize, Clone, PartialEq, Debug)]
@@ -32,43 +53,156 @@ pub struct Project {
     pub duration: String,
 }
 
+/// A task of a project, which time entries can be assigned to
 #[derive(Deserialize, Clone, Debug)]
+pub struct ProjectTask {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Workspace {
     pub id: String,
     pub name: String,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TaskInterval {
     pub start: String,
-    pub end: String,
-    pub duration: String,
+    /// Missing while the task is running
+    pub end: Option<String>,
+    pub duration: Option<String>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+impl TaskInterval {
+    /// Describes the local start and end of the task, including the end date when it differs
+    pub fn describe(&self) -> String {
+        let start = DateTime::parse_from_rfc3339(&self.start);
+        if start.is_err() {
+            return self.start.clone();
+        }
+        let start = to_local(start.unwrap().with_timezone(&Utc));
+        let end = self
+            .end
+            .as_deref()
+            .and_then(|end| DateTime::parse_from_rfc3339(end).ok())
+            .map(|end| to_local(end.with_timezone(&Utc)));
+        match end {
+            None => format!("{} - running", start.format("%Y-%m-%d %H:%M")),
+            Some(end) if end.date() == start.date() => format!(
+                "{} - {}",
+                start.format("%Y-%m-%d %H:%M"),
+                end.format("%H:%M")
+            ),
+            Some(end) => format!(
+                "{} - {}",
+                start.format("%Y-%m-%d %H:%M"),
+                end.format("%Y-%m-%d %H:%M")
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Task {
     pub id: String,
     pub description: String,
     #[serde(rename = "timeInterval")]
     pub time: TaskInterval,
+    #[serde(rename = "tagIds", default)]
+    pub tag_ids: Option<Vec<String>>,
+    #[serde(rename = "projectId", default)]
+    pub project_id: Option<String>,
+    #[serde(rename = "taskId", default)]
+    pub task_id: Option<String>,
+    #[serde(default)]
+    pub billable: bool,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+impl Task {
+    /// Builds a request for a new task with the same description, project, tags and billable
+    /// flag, running from `start` to `end` or started as a timer when there is no end
+    pub fn request(&self, start: DateTime<Utc>, end: Option<DateTime<Utc>>) -> TaskRequest {
+        TaskRequest {
+            start: start.to_rfc3339_opts(SecondsFormat::Millis, true),
+            end: end.map(|end| end.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            ..self.to_request()
+        }
+    }
+
+    /// When the task started
+    pub fn start(&self) -> Option<DateTime<Utc>> {
+        let start = DateTime::parse_from_rfc3339(&self.time.start).ok()?;
+        Some(start.with_timezone(&Utc))
+    }
+
+    /// How long the task ran, up to now while it is running
+    pub fn duration(&self) -> Duration {
+        match (self.interval(), self.start()) {
+            (Some((start, end)), _) => end - start,
+            (None, Some(start)) => Utc::now() - start,
+            (None, None) => Duration::zero(),
+        }
+    }
+
+    /// Builds a request recreating the task as it is
+    pub fn to_request(&self) -> TaskRequest {
+        TaskRequest {
+            start: self.time.start.clone(),
+            billable: self.billable,
+            description: self.description.clone(),
+            project_id: self.project_id.clone(),
+            task_id: self.task_id.clone(),
+            end: self.time.end.clone(),
+            tag_ids: self.tag_ids.clone().unwrap_or_default(),
+            custom_fields: Vec::new(),
+        }
+    }
+
+    /// The start and end of the task, `None` while it is running
+    pub fn interval(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let start = DateTime::parse_from_rfc3339(&self.time.start).ok()?;
+        let end = DateTime::parse_from_rfc3339(self.time.end.as_deref()?).ok()?;
+        Some((start.with_timezone(&Utc), end.with_timezone(&Utc)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct TaskRequest {
     pub start: String,
     pub billable: bool,
     pub description: String,
-    #[serde(rename = "projectId")]
-    pub project_id: String,
+    /// Missing for tasks without a project
+    #[serde(rename = "projectId", default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
     #[serde(rename = "taskId")]
     pub task_id: Option<String>,
-    pub end: String,
+    /// Missing when starting a timer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
     #[serde(rename = "tagIds")]
     pub tag_ids: Vec<String>,
     #[serde(rename = "customFields")]
     pub custom_fields: Vec<String>,
 }
 
+impl TaskRequest {
+    /// When the task starts and ends, without an end when it starts a timer
+    pub fn times(&self) -> Option<(DateTime<Utc>, Option<DateTime<Utc>>)> {
+        let start = DateTime::parse_from_rfc3339(&self.start).ok()?;
+        let end = match &self.end {
+            Some(end) => Some(DateTime::parse_from_rfc3339(end).ok()?.with_timezone(&Utc)),
+            None => None,
+        };
+        Some((start.with_timezone(&Utc), end))
+    }
+
+    pub fn set_times(&mut self, start: DateTime<Utc>, end: Option<DateTime<Utc>>) {
+        self.start = start.to_rfc3339_opts(SecondsFormat::Millis, true);
+        self.end = end.map(|end| end.to_rfc3339_opts(SecondsFormat::Millis, true));
+    }
+}
+
 impl Clockify {
     pub fn gen_auth_headers(&self) -> HeaderMap {
         let mut headers = HeaderMap::new();
@@ -115,6 +249,47 @@ impl Clockify {
         Some(result.unwrap())
     }
 
+    /// Finds a workspace by its id or its name
+    pub async fn find_workspace(&self, workspace: &str) -> Option<Workspace> {
+        let workspaces = self.get_workspaces().await?;
+        workspaces
+            .into_iter()
+            .find(|entry| entry.id == workspace || entry.name.eq_ignore_ascii_case(workspace))
+    }
+
+    /// Finds a project by its id or its name
+    pub async fn find_project(&self, workspace: &Workspace, project: &str) -> Option<Project> {
+        let projects = self.get_projects(workspace).await?;
+        projects
+            .into_iter()
+            .find(|entry| entry.id == project || entry.name.eq_ignore_ascii_case(project))
+    }
+
+    /// Finds the tags matching the given ids or names, unknown ones are reported and skipped
+    pub async fn find_tags(&self, workspace: &Workspace, names: &[String]) -> Vec<Tag> {
+        let tags = self.get_tags(workspace).await.unwrap_or_default();
+        let mut found = Vec::new();
+        for name in names {
+            let tag = tags
+                .iter()
+                .find(|tag| &tag.id == name || tag.name.eq_ignore_ascii_case(name));
+            if tag.is_none() {
+                println!("Tag '{}' not found, skipping it", name);
+                continue;
+            }
+            if !found.contains(tag.unwrap()) {
+                found.push(tag.unwrap().clone());
+            }
+        }
+        found
+    }
+
+    /// Finds one of the latest tasks by its id
+    pub async fn find_task(&self, workspace: &Workspace, id: &str) -> Option<Task> {
+        let tasks = self.get_tasks(workspace).await?;
+        tasks.into_iter().find(|task| task.id == id)
+    }
+
     pub async fn get_tags(&self, workspace: &Workspace) -> Option<Vec<Tag>> {
         let client = reqwest::Client::new();
         let result = client
@@ -154,6 +329,76 @@ impl Clockify {
         Some(result.unwrap())
     }
 
+    pub async fn get_project_tasks(
+        &self,
+        workspace: &Workspace,
+        project: &Project,
+    ) -> Option<Vec<ProjectTask>> {
+        let client = reqwest::Client::new();
+        let result = client
+            .get(format!(
+                "https://api.clockify.me/api/v1/workspaces/{}/projects/{}/tasks",
+                workspace.id, project.id
+            ))
+            .headers(self.gen_auth_headers())
+            .send()
+            .await
+            .unwrap()
+            .json::<Vec<ProjectTask>>()
+            .await;
+
+        if result.is_err() {
+            println!("Error: {}", result.err().unwrap());
+            return None;
+        }
+
+        Some(result.unwrap())
+    }
+
+    /// Returns the tasks starting between `start` and `end`, latest first
+    pub async fn get_tasks_between(
+        &self,
+        workspace: &Workspace,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Option<Vec<Task>> {
+        const PAGE_SIZE: usize = 200;
+        let user = self.get_user().await?;
+        let client = reqwest::Client::new();
+        let mut tasks = Vec::new();
+        for page in 1.. {
+            let result = client
+                .get(format!(
+                    "https://api.clockify.me/api/v1/workspaces/{}/user/{}/time-entries",
+                    workspace.id, user.id
+                ))
+                .query(&[
+                    ("start", start.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                    ("end", end.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                    ("page", page.to_string()),
+                    ("page-size", PAGE_SIZE.to_string()),
+                ])
+                .headers(self.gen_auth_headers())
+                .send()
+                .await
+                .unwrap()
+                .json::<Vec<Task>>()
+                .await;
+
+            if result.is_err() {
+                println!("Error: {}", result.err().unwrap());
+                return None;
+            }
+            let result = result.unwrap();
+            let last = result.len() < PAGE_SIZE;
+            tasks.extend(result);
+            if last {
+                break;
+            }
+        }
+        Some(tasks)
+    }
+
     pub async fn get_tasks(&self, workspace: &Workspace) -> Option<Vec<Task>> {
         let user = self.get_user().await.unwrap();
         let client = reqwest::Client::new();
@@ -177,6 +422,8 @@ impl Clockify {
         Some(result.unwrap())
     }
 
+    /// Creates a task and records it in the journal, saying so when the created task cannot be
+    /// read back to record it
     pub async fn new_task(&self, workspace: &Workspace, request: &TaskRequest) -> Option<bool> {
         let client = reqwest::Client::new();
         let result = client
@@ -185,15 +432,176 @@ impl Clockify {
                 workspace.id
             ))
             .headers(self.gen_auth_headers())
-            .json::<TaskRequest>(&request)
+            .json::<TaskRequest>(request)
             .send()
             .await
             .unwrap();
 
-        Some(result.status().is_success())
+        if !result.status().is_success() {
+            return Some(false);
+        }
+        match result.json::<Task>().await {
+            Ok(task) => journal::record(workspace, Operation::Create { task }),
+            Err(err) => println!(
+                "The task was created, but it could not be recorded for undo: {}",
+                err
+            ),
+        }
+        Some(true)
+    }
+
+    /// Creates a task and returns it, so it can be changed or deleted later
+    pub async fn create_task(&self, workspace: &Workspace, request: &TaskRequest) -> Option<Task> {
+        let task = self.post_task(workspace, request).await?;
+        journal::record(workspace, Operation::Create { task: task.clone() });
+        Some(task)
+    }
+
+    /// Like [`Clockify::create_task`], without recording it in the journal
+    pub async fn post_task(&self, workspace: &Workspace, request: &TaskRequest) -> Option<Task> {
+        let client = reqwest::Client::new();
+        let result = client
+            .post(format!(
+                "https://api.clockify.me/api/v1/workspaces/{}/time-entries",
+                workspace.id
+            ))
+            .headers(self.gen_auth_headers())
+            .json::<TaskRequest>(request)
+            .send()
+            .await
+            .unwrap()
+            .json::<Task>()
+            .await;
+
+        if result.is_err() {
+            println!("Error: {}", result.err().unwrap());
+            return None;
+        }
+
+        Some(result.unwrap())
+    }
+
+    /// Replaces the times and details of a task
+    pub async fn update_task(
+        &self,
+        workspace: &Workspace,
+        task: &Task,
+        request: &TaskRequest,
+    ) -> Option<Task> {
+        let after = self.put_task(workspace, task, request).await?;
+        let operation = Operation::Update {
+            before: task.clone(),
+            after: after.clone(),
+        };
+        journal::record(workspace, operation);
+        Some(after)
+    }
+
+    /// Like [`Clockify::update_task`], without recording it in the journal
+    pub async fn put_task(
+        &self,
+        workspace: &Workspace,
+        task: &Task,
+        request: &TaskRequest,
+    ) -> Option<Task> {
+        let client = reqwest::Client::new();
+        let result = client
+            .put(format!(
+                "https://api.clockify.me/api/v1/workspaces/{}/time-entries/{}",
+                workspace.id, task.id
+            ))
+            .headers(self.gen_auth_headers())
+            .json::<TaskRequest>(request)
+            .send()
+            .await
+            .unwrap()
+            .json::<Task>()
+            .await;
+
+        if result.is_err() {
+            println!("Error: {}", result.err().unwrap());
+            return None;
+        }
+
+        Some(result.unwrap())
+    }
+
+    /// Starts a timer for the task, stopping the one currently running
+    pub async fn start_timer(&self, workspace: &Workspace, request: &TaskRequest) -> Option<bool> {
+        self.stop_timer(workspace).await;
+        let request = TaskRequest {
+            end: None,
+            ..request.clone()
+        };
+        Some(self.create_task(workspace, &request).await.is_some())
+    }
+
+    /// Stops the running timer and returns the stopped task, `None` when no timer is running
+    pub async fn stop_timer(&self, workspace: &Workspace) -> Option<Task> {
+        let user = self.get_user().await?;
+        let client = reqwest::Client::new();
+        let result = client
+            .patch(format!(
+                "https://api.clockify.me/api/v1/workspaces/{}/user/{}/time-entries",
+                workspace.id, user.id
+            ))
+            .headers(self.gen_auth_headers())
+            .json(&serde_json::json!({
+                "end": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+            }))
+            .send()
+            .await
+            .unwrap();
+
+        // Answers 404 when there is no running timer
+        if !result.status().is_success() {
+            return None;
+        }
+        let after = result.json::<Task>().await.ok()?;
+        let mut before = after.clone();
+        before.time.end = None;
+        before.time.duration = None;
+        let operation = Operation::Update {
+            before,
+            after: after.clone(),
+        };
+        journal::record(workspace, operation);
+        Some(after)
+    }
+
+    pub async fn new_tag(&self, workspace: &Workspace, name: &str) -> Option<Tag> {
+        let client = reqwest::Client::new();
+        let result = client
+            .post(format!(
+                "https://api.clockify.me/api/v1/workspaces/{}/tags",
+                workspace.id
+            ))
+            .headers(self.gen_auth_headers())
+            .json(&serde_json::json!({ "name": name }))
+            .send()
+            .await
+            .unwrap()
+            .json::<Tag>()
+            .await;
+
+        if result.is_err() {
+            println!("Error: {}", result.err().unwrap());
+            return None;
+        }
+
+        Some(result.unwrap())
     }
 
     pub async fn delete_task(&self, workspace: &Workspace, task: &Task) -> Option<bool> {
+        let deleted = self.remove_task(workspace, task).await?;
+        if deleted {
+            journal::record(workspace, Operation::Delete { task: task.clone() });
+        }
+        Some(deleted)
+    }
+
+    /// Like [`Clockify::delete_task`], without recording it in the journal
+    pub async fn remove_task(&self, workspace: &Workspace, task: &Task) -> Option<bool> {
         let client = reqwest::Client::new();
         let result = client
             .delete(format!(
@@ -210,237 +618,438 @@ impl Clockify {
 }
 
 impl ClockifyCLI {
-    pub async fn select_workspace(&self) -> Option<Workspace> {
-        let workspaces = self.api.get_workspaces().await;
-        if workspaces.is_none() {
-            println!("No Workspaces found");
-            return None;
+    /// Returns the workspace pinned by the directory config, prompting for one otherwise
+    pub async fn current_workspace(&self) -> Option<Workspace> {
+        let directory = self.api.manager.directory.as_ref();
+        if let Some(workspace) = directory.and_then(|dir| dir.workspace.as_ref()) {
+            let found = self.api.find_workspace(workspace).await;
+            if found.is_some() {
+                return found;
+            }
+            println!(
+                "Workspace '{}' from {} not found, choose one instead",
+                workspace,
+                DirectoryConfig::FILE_NAME
+            );
         }
+        require_input("the workspace", "set `workspace` in .clockify.toml");
+        self.select_workspace().await
+    }
 
-        let length = workspaces.as_ref().unwrap().len();
+    /// Returns the project pinned by the directory config, prompting for one otherwise
+    pub async fn current_project(&self, workspace: &Workspace) -> Option<Project> {
+        let directory = self.api.manager.directory.as_ref();
+        if let Some(project) = directory.and_then(|dir| dir.project.as_ref()) {
+            let found = self.api.find_project(workspace, project).await;
+            if found.is_some() {
+                return found;
+            }
+            println!(
+                "Project '{}' from {} not found, choose one instead",
+                project,
+                DirectoryConfig::FILE_NAME
+            );
+        }
+        require_input("the project", "set `project` in .clockify.toml");
+        self.select_project(workspace).await
+    }
+
+    /// Returns the tags pinned by the directory config, prompting for them otherwise.
+    /// No tags are used when there is no terminal to prompt in.
+    pub async fn current_tags(&self, workspace: &Workspace) -> Option<Vec<Tag>> {
+        let directory = self.api.manager.directory.as_ref();
+        match directory.filter(|dir| !dir.tags.is_empty()) {
+            Some(dir) => Some(self.api.find_tags(workspace, &dir.tags).await),
+            None if !interactive() => Some(Vec::new()),
+            None => self.select_tags(workspace, &[]).await,
+        }
+    }
+
+    /// Returns the task with the given id, prompting for one otherwise
+    pub async fn choose_task(&self, workspace: &Workspace, id: Option<&str>) -> Option<Task> {
+        if id.is_none() {
+            require_input("the task", "pass its id");
+            return self.select_task(workspace).await;
+        }
+        let id = id.unwrap();
+        let task = self.api.find_task(workspace, id).await;
+        if task.is_none() {
+            println!("Task '{}' not found", id);
+        }
+        task
+    }
 
+    /// Looks for logged tasks overlapping the request and lets the user trim the request to
+    /// the free time, shift it after them or create it anyway. Returns `false` when aborted.
+    /// Without a terminal it fails unless `--allow-overlap` was given.
+    pub async fn resolve_overlaps(&self, workspace: &Workspace, request: &mut TaskRequest) -> bool {
         loop {
-            clear_screen();
-            println!("Select a workspace:\n");
-            for (idx, workspace) in workspaces.as_ref().unwrap().iter().enumerate() {
-                println!("[{}] {}", idx + 1, workspace.name);
+            let times = request.times();
+            if times.is_none() {
+                return true;
             }
-            cursor();
-            let data = read::<usize>();
-            if data.is_none() {
-                println!("Invalid input");
-                continue;
+            let (start, end) = times.unwrap();
+            // A timer runs until now, the running one gets stopped when it starts
+            let timer = end.is_none();
+            let end = end.unwrap_or(Utc::now().max(start));
+            let from = start - Duration::days(1);
+            let tasks = self
+                .api
+                .get_tasks_between(workspace, from, end + Duration::days(1))
+                .await;
+            if tasks.is_none() {
+                return true;
             }
-            let idx = data.unwrap();
-            if idx > length {
-                println!("Invalid input");
-                continue;
+            let overlaps = tasks
+                .unwrap()
+                .into_iter()
+                .filter(|task| !timer || task.time.end.is_some())
+                .filter_map(|task| {
+                    let other = (task.start()?, task.start()? + task.duration());
+                    (other.0 < end && other.1 > start).then_some((task, other))
+                })
+                .collect::<Vec<(Task, Interval)>>();
+            if overlaps.is_empty() {
+                return true;
+            }
+
+            println!("The task overlaps with:");
+            for (task, (other_start, other_end)) in &overlaps {
+                println!(
+                    "  {} - {} {} [{}]",
+                    to_local(*other_start).format("%Y-%m-%d %H:%M"),
+                    to_local(*other_end).format("%H:%M"),
+                    task.description,
+                    task.id
+                );
+            }
+            if self.allow_overlap {
+                println!("Creating it anyway, as --allow-overlap was given");
+                return true;
+            }
+            require_input(
+                "how to resolve the overlap",
+                "pass --allow-overlap to create it",
+            );
+
+            let after = overlaps.iter().map(|(_, other)| other.1).max().unwrap();
+            let mut free = vec![(start, end)];
+            for (_, other) in &overlaps {
+                free = subtract(free, *other);
+            }
+            let longest = free.into_iter().max_by_key(|(start, end)| *end - *start);
+            let trim = if timer { None } else { longest };
+            let mut options = Vec::new();
+            if let Some((free_start, free_end)) = trim {
+                options.push(format!(
+                    "Trim it to {} - {}",
+                    to_local(free_start).format("%H:%M"),
+                    to_local(free_end).format("%H:%M")
+                ));
+            }
+            let shift = options.len();
+            options.push(format!(
+                "Shift it to start at {}",
+                to_local(after).format("%Y-%m-%d %H:%M")
+            ));
+            options.push(String::from("Create it anyway"));
+            options.push(String::from("Abort"));
+            match self.pick("What do you want to do?", options) {
+                Some(idx) if idx < shift => {
+                    let (free_start, free_end) = trim.unwrap();
+                    request.set_times(free_start, Some(free_end));
+                }
+                Some(idx) if idx == shift => {
+                    let shifted = if timer {
+                        None
+                    } else {
+                        Some(after + (end - start))
+                    };
+                    request.set_times(after, shifted);
+                }
+                Some(idx) if idx == shift + 1 => return true,
+                _ => return false,
             }
-            return Some(workspaces.as_ref().unwrap()[idx - 1].clone());
         }
     }
 
-    pub async fn select_project(&self, workspace: &Workspace) -> Option<Project> {
-        let projects = self.api.get_projects(workspace).await;
-        if projects.is_none() {
-            println!("No projects found");
+    /// Shows a type-to-filter picker and returns the index of the chosen option,
+    /// or `None` when there are no options or the picker is cancelled with Esc
+    pub fn pick(&self, text: &str, options: Vec<String>) -> Option<usize> {
+        if options.is_empty() {
             return None;
         }
+        self.prompter.select(text, options)
+    }
 
-        let length = projects.as_ref().unwrap().len();
+    /// Like [`ClockifyCLI::pick`], allowing several options to be toggled
+    pub fn pick_many(
+        &self,
+        text: &str,
+        options: Vec<String>,
+        selected: &[usize],
+    ) -> Option<Vec<usize>> {
+        if options.is_empty() {
+            return Some(Vec::new());
+        }
+        self.prompter.multi_select(text, options, selected)
+    }
 
-        loop {
-            clear_screen();
-            println!("Select a project:\n");
-            for (idx, project) in projects.as_ref().unwrap().iter().enumerate() {
-                let tracked = parse_duration(&project.duration[2..]);
-                println!("[{}] {} [{}]", idx + 1, project.name, tracked);
-            }
-            cursor();
-            let data = read::<usize>();
-            if data.is_none() {
-                println!("Invalid input");
-                continue;
-            }
-            let idx = data.unwrap();
-            if idx > length {
-                println!("Invalid input");
-                continue;
-            }
-            return Some(projects.as_ref().unwrap()[idx - 1].clone());
+    pub async fn select_workspace(&self) -> Option<Workspace> {
+        let workspaces = self.api.get_workspaces().await;
+        if workspaces.is_none() || workspaces.as_ref().unwrap().is_empty() {
+            println!("No Workspaces found");
+            return None;
         }
+        let workspaces = workspaces.unwrap();
+        let names = workspaces.iter().map(|workspace| workspace.name.clone());
+        let idx = self.pick("Select a workspace:", names.collect())?;
+        Some(workspaces[idx].clone())
+    }
+
+    pub async fn select_project(&self, workspace: &Workspace) -> Option<Project> {
+        let projects = self.api.get_projects(workspace).await;
+        if projects.is_none() || projects.as_ref().unwrap().is_empty() {
+            println!("No projects found");
+            return None;
+        }
+        let projects = projects.unwrap();
+        let names = projects.iter().map(|project| {
+            let tracked = display_duration(Some(&project.duration));
+            format!("{} [{}]", project.name, tracked)
+        });
+        let idx = self.pick("Select a project:", names.collect())?;
+        Some(projects[idx].clone())
     }
 
     pub async fn select_task(&self, workspace: &Workspace) -> Option<Task> {
         let entries = self.api.get_tasks(workspace).await;
-        if entries.is_none() {
-            println!("No projects found");
+        if entries.is_none() || entries.as_ref().unwrap().is_empty() {
+            println!("No tasks found");
             return None;
         }
+        let entries = entries.unwrap();
+        let names = entries.iter().map(|entry| {
+            format!(
+                "{} [{}] ({})",
+                entry.description,
+                display_duration(entry.time.duration.as_deref()),
+                entry.time.describe()
+            )
+        });
+        let idx = self.pick("Select a task:", names.collect())?;
+        Some(entries[idx].clone())
+    }
 
-        let length = entries.as_ref().unwrap().len();
+    /// Lets the user toggle any number of the latest tasks
+    pub async fn select_tasks(&self, workspace: &Workspace) -> Option<Vec<Task>> {
+        let entries = self.api.get_tasks(workspace).await;
+        if entries.is_none() || entries.as_ref().unwrap().is_empty() {
+            println!("No tasks found");
+            return None;
+        }
+        let entries = entries.unwrap();
+        let names = entries.iter().map(|entry| {
+            format!(
+                "{} [{}] ({})",
+                entry.description,
+                display_duration(entry.time.duration.as_deref()),
+                entry.time.describe()
+            )
+        });
+        let chosen = self.pick_many("Select the tasks:", names.collect(), &[])?;
+        Some(chosen.into_iter().map(|idx| entries[idx].clone()).collect())
+    }
 
-        loop {
-            clear_screen();
-            println!("Select a task:\n");
-            for (idx, entry) in entries.as_ref().unwrap().iter().enumerate() {
-                println!(
-                    "[{}] {} [{}]",
-                    idx + 1,
-                    entry.description,
-                    parse_duration(&entry.time.duration[2..])
-                );
+    /// Returns the ids of the tags used by the latest tasks, most recent first
+    pub async fn recent_tags(&self, workspace: &Workspace) -> Vec<String> {
+        let mut recent = Vec::new();
+        let tasks = self.api.get_tasks(workspace).await.unwrap_or_default();
+        for id in tasks
+            .into_iter()
+            .flat_map(|task| task.tag_ids.unwrap_or_default())
+        {
+            if !recent.contains(&id) {
+                recent.push(id);
             }
-            cursor();
-            let data = read::<usize>();
-            if data.is_none() {
-                println!("Invalid input");
-                continue;
-            }
-            let idx = data.unwrap();
-            if idx > length {
-                println!("Invalid input");
-                continue;
-            }
-            return Some(entries.as_ref().unwrap()[idx - 1].clone());
         }
+        recent.truncate(RECENT_TAGS);
+        recent
     }
 
-    pub async fn select_tags(&self, workspace: &Workspace) -> Option<Vec<Tag>> {
-        let mut selected_tags: Vec<Tag> = Vec::new();
+    /// Lets the user toggle the tags of a task, starting with the `selected` tag ids.
+    /// Recently used tags are listed first and missing tags can be created on the fly.
+    pub async fn select_tags(
+        &self,
+        workspace: &Workspace,
+        selected: &[String],
+    ) -> Option<Vec<Tag>> {
         let tags = self.api.get_tags(workspace).await;
         if tags.is_none() {
             println!("No tags found");
             return None;
         }
+        let mut tags = tags.unwrap();
+        let recent = self.recent_tags(workspace).await;
+        tags.sort_by_key(|tag| {
+            recent
+                .iter()
+                .position(|id| id == &tag.id)
+                .unwrap_or(usize::MAX)
+        });
 
-        let length = tags.as_ref().unwrap().len();
-
-        loop {
-            clear_screen();
-            println!("Select a tag:\n");
-            for (idx, tag) in tags.as_ref().unwrap().iter().enumerate() {
-                let selected = if selected_tags.contains(tag) {
-                    "X"
-                } else {
-                    " "
-                };
-                println!("[{}] {} [{}]", idx + 1, tag.name, selected);
-            }
-            println!("[0] Continue");
-            cursor();
-            let data = read::<usize>();
-            if data.is_none() {
-                println!("Invalid input");
-                continue;
-            }
-            let idx = data.unwrap();
-            if idx == 0 {
-                return Some(selected_tags);
+        let mut names = vec![RECENT_OPTION.to_string(), CREATE_OPTION.to_string()];
+        names.extend(tags.iter().map(|tag| {
+            if recent.contains(&tag.id) {
+                format!("{} (recent)", tag.name)
+            } else {
+                tag.name.clone()
             }
-            if idx > length {
-                println!("Invalid tag");
-                continue;
+        }));
+        let defaults = tags
+            .iter()
+            .enumerate()
+            .filter(|(_, tag)| selected.contains(&tag.id))
+            .map(|(idx, _)| idx + 2)
+            .collect::<Vec<usize>>();
+        let chosen = self.pick_many("Select the tags:", names, &defaults)?;
+
+        let mut result = chosen
+            .iter()
+            .filter(|idx| **idx >= 2)
+            .map(|idx| tags[idx - 2].clone())
+            .collect::<Vec<Tag>>();
+        if chosen.contains(&0) {
+            for tag in tags.iter().filter(|tag| recent.contains(&tag.id)) {
+                if !result.contains(tag) {
+                    result.push(tag.clone());
+                }
             }
-            if selected_tags.contains(&tags.as_ref().unwrap()[idx - 1]) {
-                selected_tags.remove(idx - 1);
-                continue;
+        }
+        if chosen.contains(&1) {
+            let names = self
+                .select_text("Enter the names of the new tags, separated by commas")
+                .await
+                .unwrap_or_default();
+            for name in names
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+            {
+                let existing = tags.iter().find(|tag| tag.name.eq_ignore_ascii_case(name));
+                let tag = match existing {
+                    Some(tag) => Some(tag.clone()),
+                    None => self.api.new_tag(workspace, name).await,
+                };
+                if tag.is_none() {
+                    println!("Failed to create tag '{}'", name);
+                    continue;
+                }
+                if !result.contains(tag.as_ref().unwrap()) {
+                    result.push(tag.unwrap());
+                }
             }
-            selected_tags.push(tags.as_ref().unwrap()[idx - 1].clone());
         }
+        Some(result)
     }
 
-    pub async fn select_text_opt(text: &str, default: Option<&str>) -> Option<String> {
-        let mut selected_text = default.unwrap_or("").to_string();
-        loop {
-            clear_screen();
-            if selected_text.is_empty() {
-                println!("{}:\n", text);
-            } else {
-                println!("{} [{}]:\n", text, selected_text);
-            }
-            println!("[0] Continue");
-            cursor();
-            let data = read::<String>();
-            if data.is_none() {
-                println!("Invalid input");
-                continue;
-            }
-            let description = data.unwrap();
-            if description == "0" {
-                return Some(selected_text);
-            }
-            selected_text = description;
-        }
+    /// Asks for a text, pre-filled with `default`. Returns `None` when cancelled with Esc.
+    pub async fn select_text_opt(&self, text: &str, default: Option<&str>) -> Option<String> {
+        self.prompter.text(text, default, None, None)
     }
 
-    pub async fn select_text(text: &str) -> Option<String> {
-        return Self::select_text_opt(text, None).await;
+    pub async fn select_text(&self, text: &str) -> Option<String> {
+        return self.select_text_opt(text, None).await;
     }
 
-    pub async fn select_time(start: Option<NaiveTime>) -> Option<NaiveTime> {
-        let mut selected_time: Option<NaiveTime> = None;
+    /// Asks for the start time, or the end time when `start` is given.
+    /// Returns `None` when cancelled with Esc.
+    pub async fn select_time(
+        &self,
+        start: Option<NaiveTime>,
+        default: Option<NaiveTime>,
+    ) -> Option<NaiveTime> {
+        let (_, time) = self.read_time(start, default).await?;
+        Some(time)
+    }
+
+    /// Asks for the end time of a task starting at `start`, along with the duration when one
+    /// was typed instead of a time. Returns `None` when cancelled with Esc.
+    pub async fn select_end(
+        &self,
+        start: NaiveTime,
+        default: Option<NaiveTime>,
+    ) -> Option<(NaiveTime, Option<Duration>)> {
+        let (input, time) = self.read_time(Some(start), default).await?;
+        Some((time, parse_end_span(&input)))
+    }
+
+    /// Asks for a time, returning what was typed along with the time it stands for
+    async fn read_time(
+        &self,
+        start: Option<NaiveTime>,
+        default: Option<NaiveTime>,
+    ) -> Option<(String, NaiveTime)> {
+        let text = if start.is_some() {
+            "Enter the end time:"
+        } else {
+            "Enter the start time:"
+        };
+        let help = if start.is_some() {
+            "e.g. 17:30, 530pm, now, -15m or a duration such as 1h30m"
+        } else {
+            "e.g. 9:30, 930, 9:30am, now, -15m"
+        };
+        let default = default.map(|time| time.format("%H:%M").to_string());
         loop {
-            clear_screen();
-            if selected_time.is_none() {
-                println!(
-                    "Please enter the {} time (HH:MM):",
-                    if start.is_some() { "end" } else { "start" }
-                );
-            } else {
-                println!(
-                    "Please enter the {} time ({}):",
-                    if start.is_some() { "end" } else { "start" },
-                    selected_time.unwrap()
-                );
-            }
-            println!("[0] Continue");
-            cursor();
-            let data = read::<String>();
-            if data.is_none() {
-                println!("Invalid input");
-                continue;
-            }
-            let description = data.unwrap();
-            if description == "0" {
-                return selected_time;
-            }
-            let time = NaiveTime::parse_from_str(&description, "%H:%M");
-            if time.is_err() {
-                println!("Invalid time");
+            let validator = Rc::new(move |input: &str| match parse_time(input, start) {
+                Some(_) => Ok(()),
+                None => Err(String::from("Invalid time")),
+            });
+            let input =
+                self.prompter
+                    .text(text, default.as_deref(), Some(help), Some(validator))?;
+            let time = parse_time(&input, start)?;
+            if start.is_some()
+                && start.unwrap() > time
+                && !self.select_bool(
+                    "The end time is before the start time, does the task end on the next day?",
+                    "pass --end-date",
+                )
+            {
                 continue;
             }
-            if start.is_some() && start.unwrap() > time.unwrap() {
-                println!("Start time cannot be after end time");
-                continue;
-            }
-            selected_time = Some(time.unwrap());
+            return Some((input, time));
         }
     }
-    pub fn select_bool(text: &str) -> bool {
-        let mut status: bool = false;
-        loop {
-            clear_screen();
-            println!("{} (y/n): {}\n", text, if status { "Yes" } else { "No" });
-            println!("[0] Continue");
-            cursor();
-            let data = read::<String>();
-            if data.is_none() {
-                println!("Invalid input");
-                continue;
-            }
-            let description = data.unwrap();
-            if description == "0" {
-                return status;
-            }
-            if description == "y" {
-                status = true;
-                continue;
-            }
-            if description == "n" {
-                status = false;
-                continue;
+
+    /// Asks for the date of the task, typing `c` opens a calendar.
+    /// Returns `None` when cancelled with Esc.
+    pub fn select_date(&self, default: NaiveDate) -> Option<NaiveDate> {
+        let help = "e.g. today, yesterday, mon, last friday, -3d, 2023-04-03 or c for a calendar";
+        let validator = Rc::new(|input: &str| {
+            if input.trim() == "c" || parse_date(input).is_some() {
+                return Ok(());
             }
+            Err(String::from("Invalid date"))
+        });
+        let input = self.prompter.text(
+            "Enter the date of the task:",
+            Some(&default.format("%Y-%m-%d").to_string()),
+            Some(help),
+            Some(validator),
+        )?;
+        if input.trim() == "c" {
+            return self.prompter.calendar("Select a date:", default);
         }
+        parse_date(&input)
+    }
+
+    /// Asks a yes or no question, cancelling with Esc answers no. Without a terminal it exits
+    /// naming the flag given by `hint` that answers it.
+    pub fn select_bool(&self, text: &str, hint: &str) -> bool {
+        let question = text.lines().last().unwrap_or_default();
+        require_input(&format!("'{}'", question), hint);
+        self.prompter.confirm(text).unwrap_or(false)
     }
 }
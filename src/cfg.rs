@@ -1,19 +1,152 @@
-use std::fs;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
-use chrono::NaiveTime;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
-use crate::api::TaskRequest;
+use crate::{
+    api::TaskRequest,
+    utils::{datetime, now, DurationFormat},
+};
 
 #[derive(Clone, Debug)]
 pub struct ConfigManager {
     pub config: Option<Config>,
+    pub directory: Option<DirectoryConfig>,
+}
+
+/// Defaults pinned by a `.clockify.toml` in the current directory or one of its parents
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct DirectoryConfig {
+    /// The id or name of the workspace
+    pub workspace: Option<String>,
+    /// The id or name of the project
+    pub project: Option<String>,
+    /// The ids or names of the tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub billable: Option<bool>,
+    pub description_prefix: Option<String>,
+}
+
+impl DirectoryConfig {
+    pub const FILE_NAME: &'static str = ".clockify.toml";
+
+    /// Looks for the closest `.clockify.toml`, starting in the current directory
+    pub fn find() -> Option<PathBuf> {
+        let mut dir = env::current_dir().ok()?;
+        loop {
+            let file = dir.join(Self::FILE_NAME);
+            if file.is_file() {
+                return Some(file);
+            }
+            if !dir.pop() {
+                return None;
+            }
+        }
+    }
+
+    pub fn load(file: &Path) -> Option<DirectoryConfig> {
+        let contents = fs::read_to_string(file).ok()?;
+        let config = toml::from_str(&contents);
+        if config.is_err() {
+            println!("Failed to parse {}: {}", file.display(), config.err().unwrap());
+            return None;
+        }
+        Some(config.unwrap())
+    }
+
+    /// Prepends the description prefix, if any
+    pub fn describe(&self, description: &str) -> String {
+        match &self.description_prefix {
+            Some(prefix) if !description.starts_with(prefix.as_str()) => {
+                format!("{} {}", prefix.trim_end(), description)
+            }
+            _ => description.to_string(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Config {
     pub api_key: String,
     pub saved_tasks: Vec<SavedTask>,
+    /// The IANA timezone used for the times of the tasks, the system one when unset
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub duration_format: DurationFormat,
+    /// The first day of the week in reports, Monday when unset
+    #[serde(default)]
+    pub week_start: Option<Weekday>,
+    /// Rounds the durations in reports to this many minutes
+    #[serde(default)]
+    pub rounding: Option<u32>,
+    /// The hours expected to be logged on a working day
+    #[serde(default)]
+    pub daily_target: Option<f64>,
+    /// Days off besides the weekends
+    #[serde(default)]
+    pub holidays: Vec<NaiveDate>,
+    /// The hours expected to be logged, Monday to Friday from 09:00 to 17:30 when unset
+    #[serde(default)]
+    pub working_hours: Option<WorkingHours>,
+}
+
+/// When the working days start and end, used to find the time that was not logged
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WorkingHours {
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    /// A break in the working day that is not expected to be logged
+    #[serde(default)]
+    pub lunch: Option<(NaiveTime, NaiveTime)>,
+}
+
+impl Default for WorkingHours {
+    fn default() -> Self {
+        WorkingHours {
+            days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+            start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(17, 30, 0).unwrap(),
+            lunch: None,
+        }
+    }
+}
+
+impl WorkingHours {
+    pub fn describe(&self) -> String {
+        let days = self.days.iter().map(|day| day.to_string());
+        let mut text = format!(
+            "{} from {} to {}",
+            days.collect::<Vec<String>>().join(", "),
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        );
+        if let Some((start, end)) = self.lunch {
+            text.push_str(&format!(
+                ", lunch from {} to {}",
+                start.format("%H:%M"),
+                end.format("%H:%M")
+            ));
+        }
+        text
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TemplateFile {
+    pub saved_tasks: Vec<SavedTask>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -22,6 +155,94 @@ pub struct SavedTask {
     pub name: String,
     pub start: NaiveTime,
     pub end: NaiveTime,
+    #[serde(default)]
+    pub workspace_id: Option<String>,
+    /// When set, the task is placed relative to the moment it gets applied
+    #[serde(default)]
+    pub relative: Option<RelativeTime>,
+    /// Names of extra tags, which may contain `{placeholders}`
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Where a relative template is placed when it gets applied
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum TemplateAnchor {
+    /// The task ends at the moment the template is applied
+    EndNow,
+    /// The task starts at the moment the template is applied
+    StartNow,
+    /// The task starts when the last registered task ended
+    AfterLast,
+    /// The task starts at the start time of the template
+    StartAt,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RelativeTime {
+    pub anchor: TemplateAnchor,
+    pub minutes: i64,
+}
+
+impl RelativeTime {
+    pub fn describe(&self, start: NaiveTime) -> String {
+        match self.anchor {
+            TemplateAnchor::EndNow => format!("{} minutes ending now", self.minutes),
+            TemplateAnchor::StartNow => format!("{} minutes starting now", self.minutes),
+            TemplateAnchor::AfterLast => {
+                format!("{} minutes after the last task", self.minutes)
+            }
+            TemplateAnchor::StartAt => {
+                format!("{} minutes starting at {}", self.minutes, start.format("%H:%M"))
+            }
+        }
+    }
+}
+
+impl SavedTask {
+    /// Describes when the template places the task
+    pub fn describe_time(&self) -> String {
+        match &self.relative {
+            Some(relative) => relative.describe(self.start),
+            None => format!("{} - {}", self.start.format("%H:%M"), self.end.format("%H:%M")),
+        }
+    }
+
+    /// Computes the start and end of the task on the given date.
+    /// `last_end` is the end of the last registered task, used by [`TemplateAnchor::AfterLast`].
+    pub fn resolve(
+        &self,
+        date: NaiveDate,
+        last_end: Option<DateTime<Utc>>,
+    ) -> (DateTime<Utc>, DateTime<Utc>) {
+        let relative = match &self.relative {
+            Some(relative) => relative,
+            None => {
+                // A template ending before it starts spans midnight
+                let end_date = if self.end < self.start {
+                    date + Duration::days(1)
+                } else {
+                    date
+                };
+                return (datetime(date, self.start), datetime(end_date, self.end));
+            }
+        };
+        let now = datetime(date, now().time());
+        let duration = Duration::minutes(relative.minutes);
+        match relative.anchor {
+            TemplateAnchor::EndNow => (now - duration, now),
+            TemplateAnchor::StartNow => (now, now + duration),
+            TemplateAnchor::AfterLast => {
+                let start = last_end.unwrap_or(now);
+                (start, start + duration)
+            }
+            TemplateAnchor::StartAt => {
+                let start = datetime(date, self.start);
+                (start, start + duration)
+            }
+        }
+    }
 }
 
 impl ConfigManager {
@@ -36,16 +257,60 @@ impl ConfigManager {
         config.saved_tasks.clone()
     }
 
+    pub fn get_saved_task(&self, name: &str) -> Option<SavedTask> {
+        let config = self.config.as_ref().unwrap();
+        config.saved_tasks.iter().find(|task| task.name == name).cloned()
+    }
+
+    pub fn update_saved_task(&mut self, name: &str, task: SavedTask) -> bool {
+        let config = self.config.as_mut().unwrap();
+        let entry = config.saved_tasks.iter_mut().find(|saved| saved.name == name);
+        if entry.is_none() {
+            return false;
+        }
+        *entry.unwrap() = task;
+        true
+    }
+
+    pub fn remove_saved_task(&mut self, name: &str) -> Option<SavedTask> {
+        let config = self.config.as_mut().unwrap();
+        let idx = config.saved_tasks.iter().position(|task| task.name == name)?;
+        Some(config.saved_tasks.remove(idx))
+    }
+
+    /// Adds the given templates, replacing the ones that share a name.
+    /// Returns the amount of templates that were replaced.
+    pub fn import_saved_tasks(&mut self, tasks: Vec<SavedTask>) -> usize {
+        let mut replaced = 0;
+        for task in tasks {
+            let name = task.name.clone();
+            if self.update_saved_task(&name, task.clone()) {
+                replaced += 1;
+                continue;
+            }
+            self.save_task(task);
+        }
+        replaced
+    }
+
     pub fn validate(&self) -> bool {
-        let exists = fs::try_exists("Config.toml");
+        let exists = Path::new("Config.toml").try_exists();
         exists.is_ok() && exists.unwrap()
     }
     pub fn load(&mut self) {
+        self.directory = DirectoryConfig::find().and_then(|file| DirectoryConfig::load(&file));
         let exists = self.validate();
         if !exists {
             self.config = Some(Config {
                 api_key: String::new(),
                 saved_tasks: Vec::new(),
+                timezone: None,
+                duration_format: DurationFormat::default(),
+                week_start: None,
+                rounding: None,
+                daily_target: None,
+                holidays: Vec::new(),
+                working_hours: None,
             });
             return;
         }
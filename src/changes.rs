@@ -0,0 +1,106 @@
+use crate::{
+    api::{Clockify, Task, TaskRequest, Workspace},
+    journal::{self, Operation},
+};
+
+/// A change made to the tasks, with what is needed to undo it
+#[derive(Clone, Debug)]
+pub enum Change {
+    Created(Task),
+    /// Holds the task as it was before the update
+    Updated(Task),
+    Deleted(Task),
+}
+
+impl Change {
+    /// Whether the journal operation is the one recorded for this change
+    fn recorded_as(&self, operation: &Operation) -> bool {
+        match (self, operation) {
+            (Change::Created(task), Operation::Create { task: recorded }) => task.id == recorded.id,
+            (Change::Updated(task), Operation::Update { before, .. }) => task.id == before.id,
+            (Change::Deleted(task), Operation::Delete { task: recorded }) => task.id == recorded.id,
+            _ => false,
+        }
+    }
+}
+
+/// Applies several changes to the tasks as one, undoing the applied ones when a step fails
+pub struct ChangeSet<'a> {
+    api: &'a Clockify,
+    workspace: &'a Workspace,
+    applied: Vec<Change>,
+}
+
+impl<'a> ChangeSet<'a> {
+    pub fn new(api: &'a Clockify, workspace: &'a Workspace) -> ChangeSet<'a> {
+        ChangeSet {
+            api,
+            workspace,
+            applied: Vec::new(),
+        }
+    }
+
+    pub async fn create(&mut self, request: &TaskRequest) -> Option<Task> {
+        let task = self.api.create_task(self.workspace, request).await?;
+        self.applied.push(Change::Created(task.clone()));
+        Some(task)
+    }
+
+    pub async fn update(&mut self, task: &Task, request: &TaskRequest) -> Option<Task> {
+        let updated = self.api.update_task(self.workspace, task, request).await?;
+        self.applied.push(Change::Updated(task.clone()));
+        Some(updated)
+    }
+
+    pub async fn delete(&mut self, task: &Task) -> Option<()> {
+        let deleted = self.api.delete_task(self.workspace, task).await;
+        if !deleted.unwrap_or(false) {
+            return None;
+        }
+        self.applied.push(Change::Deleted(task.clone()));
+        Some(())
+    }
+
+    /// Undoes the applied changes, latest first, and drops the undone ones from the journal.
+    /// Returns whether every change was undone.
+    pub async fn rollback(self) -> bool {
+        let mut undone = Vec::new();
+        for change in self.applied.iter().rev() {
+            undone.push(match change {
+                Change::Created(task) => self
+                    .api
+                    .remove_task(self.workspace, task)
+                    .await
+                    .unwrap_or(false),
+                Change::Updated(task) => {
+                    let request = task.to_request();
+                    let restored = self.api.put_task(self.workspace, task, &request);
+                    restored.await.is_some()
+                }
+                Change::Deleted(task) => {
+                    let request = task.to_request();
+                    let restored = self.api.post_task(self.workspace, &request);
+                    restored.await.is_some()
+                }
+            });
+        }
+        undone.reverse();
+
+        let mut entries = journal::load();
+        for (change, _) in self
+            .applied
+            .iter()
+            .zip(&undone)
+            .filter(|(_, undone)| **undone)
+        {
+            let recorded = entries
+                .iter()
+                .rposition(|entry| change.recorded_as(&entry.operation));
+            if let Some(recorded) = recorded {
+                entries.remove(recorded);
+            }
+        }
+        journal::save(&entries);
+        undone.into_iter().all(|undone| undone)
+    }
+}
@@ -1,4 +1,13 @@
+pub mod audit;
+pub mod bulk;
+pub mod calendar;
 pub mod config;
+pub mod gaps;
+pub mod history;
+pub mod log;
 pub mod projects;
 pub mod tags;
 pub mod task;
+pub mod template;
+pub mod timesheet;
+pub mod undo;
@@ -0,0 +1,103 @@
+use chrono::{Duration, NaiveTime};
+use clap::{Parser, Subcommand};
+
+use crate::{
+    api::{ClockifyCLI, Task},
+    utils::{datetime, format_duration, now, parse_date, to_local, Interval},
+};
+
+/// Check the logged tasks for problems
+#[derive(Debug, Parser)]
+pub struct AuditCommand {
+    #[clap(subcommand)]
+    command: AuditSubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AuditSubCommand {
+    /// List the tasks that overlap each other
+    Overlaps {
+        /// The first day to check (e.g. -4w, 2023-04-01), defaults to four weeks ago
+        #[clap(long)]
+        since: Option<String>,
+    },
+}
+
+fn describe(task: &Task, interval: &Interval) -> String {
+    format!(
+        "{} - {} {} [{}]",
+        to_local(interval.0).format("%H:%M"),
+        to_local(interval.1).format("%H:%M"),
+        task.description,
+        task.id
+    )
+}
+
+impl AuditCommand {
+    pub async fn run(&self, cli: &ClockifyCLI) {
+        match &self.command {
+            AuditSubCommand::Overlaps { since } => {
+                let today = now().date();
+                let since = match since {
+                    Some(since) => parse_date(since),
+                    None => Some(today - Duration::weeks(4)),
+                };
+                if since.is_none() {
+                    println!("Invalid date");
+                    return;
+                }
+                let since = since.unwrap();
+                let workspace = cli.current_workspace().await;
+                if workspace.is_none() {
+                    return;
+                }
+                let workspace = workspace.unwrap();
+                let from = datetime(since, NaiveTime::MIN);
+                let to = datetime(today + Duration::days(1), NaiveTime::MIN);
+                let tasks = cli.api.get_tasks_between(&workspace, from, to).await;
+                if tasks.is_none() {
+                    return;
+                }
+                let mut tasks = tasks
+                    .unwrap()
+                    .into_iter()
+                    .filter_map(|task| {
+                        let start = task.start()?;
+                        let end = start + task.duration();
+                        Some((task, (start, end)))
+                    })
+                    .collect::<Vec<(Task, Interval)>>();
+                tasks.sort_by_key(|(_, interval)| interval.0);
+
+                let mut found = 0;
+                let mut total = Duration::zero();
+                for (idx, (task, interval)) in tasks.iter().enumerate() {
+                    let later = tasks[idx + 1..].iter();
+                    for (other, other_interval) in later.take_while(|(_, next)| next.0 < interval.1)
+                    {
+                        let overlap = interval.1.min(other_interval.1) - other_interval.0;
+                        println!(
+                            "{} {}\n           overlaps {} by {}",
+                            to_local(interval.0).format("%Y-%m-%d"),
+                            describe(task, interval),
+                            describe(other, other_interval),
+                            format_duration(overlap)
+                        );
+                        found += 1;
+                        total += overlap;
+                    }
+                }
+                if found == 0 {
+                    println!("No overlapping tasks since {}", since);
+                    return;
+                }
+                println!(
+                    "\n{} overlaps found since {}, {} logged more than once",
+                    found,
+                    since,
+                    format_duration(total)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate, NaiveTime};
+use clap::Args;
+use regex::Regex;
+
+use crate::{
+    api::{Clockify, ClockifyCLI, ProjectTask, Task, Workspace},
+    changes::ChangeSet,
+    utils::{datetime, format_duration, now, parse_date, require_input},
+};
+
+/// Selects tasks by date, project, tag or description
+#[derive(Debug, Args)]
+pub struct TaskFilter {
+    /// Only tasks starting on or after this date (e.g. 2023-04-03, -1w, last monday)
+    #[clap(long)]
+    since: Option<String>,
+    /// Only tasks starting on or before this date
+    #[clap(long)]
+    until: Option<String>,
+    /// Only tasks of this project, by id or name
+    #[clap(long)]
+    project: Option<String>,
+    /// Only tasks with this tag, by id or name
+    #[clap(long)]
+    tag: Option<String>,
+    /// Only tasks whose description matches this regular expression
+    #[clap(long, value_name = "REGEX")]
+    search: Option<String>,
+}
+
+impl TaskFilter {
+    /// Whether no filter was given
+    pub fn is_empty(&self) -> bool {
+        self.since.is_none()
+            && self.until.is_none()
+            && self.project.is_none()
+            && self.tag.is_none()
+            && self.search.is_none()
+    }
+
+    /// Fetches the tasks matching the filter. Without dates, only the latest tasks are searched.
+    /// Returns `None` when one of the filters is invalid.
+    pub async fn tasks(&self, api: &Clockify, workspace: &Workspace) -> Option<Vec<Task>> {
+        let since = self.since.as_deref().map(parse_date);
+        let until = self.until.as_deref().map(parse_date);
+        if since.is_some_and(|since| since.is_none()) || until.is_some_and(|until| until.is_none())
+        {
+            println!("Invalid date");
+            return None;
+        }
+        let search = self.search.as_deref().map(Regex::new).transpose();
+        if let Err(err) = &search {
+            println!("Invalid search: {}", err);
+            return None;
+        }
+        let search = search.unwrap();
+        let project = match &self.project {
+            Some(project) => {
+                let found = api.find_project(workspace, project).await;
+                if found.is_none() {
+                    println!("Project '{}' not found", project);
+                    return None;
+                }
+                found
+            }
+            None => None,
+        };
+        let tag = match &self.tag {
+            Some(tag) => {
+                let found = api.find_tags(workspace, std::slice::from_ref(tag)).await;
+                Some(found.into_iter().next()?)
+            }
+            None => None,
+        };
+
+        let tasks = match (since.flatten(), until.flatten()) {
+            (None, None) => api.get_tasks(workspace).await?,
+            (since, until) => {
+                let since = since.unwrap_or(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap());
+                let until = until.unwrap_or(now().date());
+                let start = datetime(since, NaiveTime::MIN);
+                let end = datetime(until + Duration::days(1), NaiveTime::MIN);
+                api.get_tasks_between(workspace, start, end).await?
+            }
+        };
+        let tasks = tasks.into_iter().filter(|task| {
+            project
+                .as_ref()
+                .is_none_or(|project| task.project_id.as_ref() == Some(&project.id))
+                && tag.as_ref().is_none_or(|tag| {
+                    task.tag_ids
+                        .as_ref()
+                        .is_some_and(|ids| ids.contains(&tag.id))
+                })
+                && search
+                    .as_ref()
+                    .is_none_or(|search| search.is_match(&task.description))
+        });
+        Some(tasks.collect())
+    }
+}
+
+/// Change the project, task, tags, billable flag or description of many tasks at once
+#[derive(Debug, Args)]
+pub struct BulkEditCommand {
+    #[clap(flatten)]
+    filter: TaskFilter,
+    /// Moves the tasks to this project, by id or name
+    #[clap(long, value_name = "PROJECT")]
+    set_project: Option<String>,
+    /// Assigns the tasks to this task of their project, by id or name
+    #[clap(long, value_name = "TASK")]
+    set_task: Option<String>,
+    /// Adds a tag to the tasks
+    #[clap(long, value_name = "NAME")]
+    add_tag: Vec<String>,
+    /// Removes a tag from the tasks
+    #[clap(long, value_name = "NAME")]
+    remove_tag: Vec<String>,
+    /// Sets whether the tasks are billable
+    #[clap(long)]
+    billable: Option<bool>,
+    /// Rewrites the part of the descriptions matching this regular expression
+    #[clap(long, value_name = "REGEX", requires = "with")]
+    replace: Option<String>,
+    /// The replacement for --replace, groups can be used as $1
+    #[clap(long, value_name = "TEXT", requires = "replace")]
+    with: Option<String>,
+    /// Applies the changes without asking for confirmation
+    #[clap(short, long)]
+    yes: bool,
+}
+
+/// Describes the change of a field, or nothing when it stays the same
+fn describe_change(field: &str, old: &str, new: &str) -> Option<String> {
+    if old == new {
+        return None;
+    }
+    Some(format!("    {}: {} -> {}", field, old, new))
+}
+
+impl BulkEditCommand {
+    pub async fn run(&self, cli: &ClockifyCLI) {
+        let api = &cli.api;
+        if self.set_project.is_none()
+            && self.set_task.is_none()
+            && self.add_tag.is_empty()
+            && self.remove_tag.is_empty()
+            && self.billable.is_none()
+            && self.replace.is_none()
+        {
+            println!("Nothing to change, pass --set-project, --set-task, --add-tag, --remove-tag, --billable or --replace");
+            return;
+        }
+        let replace = self.replace.as_deref().map(Regex::new).transpose();
+        if let Err(err) = &replace {
+            println!("Invalid --replace: {}", err);
+            return;
+        }
+        let replace = replace.unwrap();
+
+        let workspace = cli.current_workspace().await;
+        if workspace.is_none() {
+            return;
+        }
+        let workspace = workspace.unwrap();
+        // Without filters the tasks are picked, rather than changing all the latest ones
+        let tasks = if self.filter.is_empty() {
+            require_input(
+                "the tasks",
+                "pass filters such as --since, --project, --tag or --search",
+            );
+            cli.select_tasks(&workspace).await
+        } else {
+            self.filter.tasks(api, &workspace).await
+        };
+        if tasks.is_none() {
+            return;
+        }
+        let (tasks, running): (Vec<Task>, Vec<Task>) = tasks
+            .unwrap()
+            .into_iter()
+            .partition(|task| task.interval().is_some());
+        if !running.is_empty() {
+            println!("Skipping the running task '{}'", running[0].description);
+        }
+
+        let projects = api.get_projects(&workspace).await.unwrap_or_default();
+        let set_project = match &self.set_project {
+            Some(name) => {
+                let project = projects
+                    .iter()
+                    .find(|project| &project.id == name || project.name.eq_ignore_ascii_case(name));
+                if project.is_none() {
+                    println!("Project '{}' not found", name);
+                    return;
+                }
+                project.cloned()
+            }
+            None => None,
+        };
+        let add_tags = api.find_tags(&workspace, &self.add_tag).await;
+        let remove_tags = api.find_tags(&workspace, &self.remove_tag).await;
+        if add_tags.len() < self.add_tag.len() || remove_tags.len() < self.remove_tag.len() {
+            return;
+        }
+        let tag_names = api.get_tags(&workspace).await.unwrap_or_default();
+        let tag_names = tag_names
+            .into_iter()
+            .map(|tag| (tag.id, tag.name))
+            .collect::<HashMap<String, String>>();
+        let project_name = |id: &str| {
+            let project = projects.iter().find(|project| project.id == id);
+            project.map_or(String::from("none"), |project| project.name.clone())
+        };
+        let tags_name = |ids: &[String]| {
+            let names = ids
+                .iter()
+                .map(|id| tag_names.get(id).unwrap_or(id).as_str());
+            let names = names.collect::<Vec<&str>>().join(", ");
+            if names.is_empty() {
+                String::from("none")
+            } else {
+                names
+            }
+        };
+
+        let mut project_tasks: HashMap<String, Vec<ProjectTask>> = HashMap::new();
+        let mut changes = Vec::new();
+        for task in &tasks {
+            let old = task.to_request();
+            let mut request = old.clone();
+            if let Some(project) = &set_project {
+                if request.project_id.as_ref() != Some(&project.id) {
+                    request.project_id = Some(project.id.clone());
+                    request.task_id = None;
+                }
+            }
+            let mut task_name = None;
+            if let Some(name) = &self.set_task {
+                let project_id = request.project_id.clone().unwrap_or_default();
+                if !project_tasks.contains_key(&project_id) {
+                    let project = projects.iter().find(|project| project.id == project_id);
+                    let found = match project {
+                        Some(project) => api.get_project_tasks(&workspace, project).await,
+                        None => None,
+                    };
+                    project_tasks.insert(project_id.clone(), found.unwrap_or_default());
+                }
+                let found = project_tasks[&project_id]
+                    .iter()
+                    .find(|entry| &entry.id == name || entry.name.eq_ignore_ascii_case(name));
+                if found.is_none() {
+                    println!(
+                        "Task '{}' not found in project {}",
+                        name,
+                        project_name(&project_id)
+                    );
+                    return;
+                }
+                request.task_id = Some(found.unwrap().id.clone());
+                task_name = Some(found.unwrap().name.clone());
+            }
+            request
+                .tag_ids
+                .retain(|id| !remove_tags.iter().any(|tag| &tag.id == id));
+            for tag in &add_tags {
+                if !request.tag_ids.contains(&tag.id) {
+                    request.tag_ids.push(tag.id.clone());
+                }
+            }
+            if let Some(billable) = self.billable {
+                request.billable = billable;
+            }
+            if let Some(replace) = &replace {
+                let with = self.with.as_deref().unwrap_or_default();
+                request.description = replace.replace_all(&request.description, with).to_string();
+            }
+            if request == old {
+                continue;
+            }
+
+            let yes_no = |billable: bool| if billable { "yes" } else { "no" };
+            let lines = [
+                describe_change("description", &old.description, &request.description),
+                describe_change(
+                    "project",
+                    &project_name(old.project_id.as_deref().unwrap_or_default()),
+                    &project_name(request.project_id.as_deref().unwrap_or_default()),
+                ),
+                describe_change(
+                    "task",
+                    old.task_id.as_deref().unwrap_or("none"),
+                    task_name
+                        .as_deref()
+                        .or(request.task_id.as_deref())
+                        .unwrap_or("none"),
+                )
+                .filter(|_| old.task_id != request.task_id),
+                describe_change(
+                    "tags",
+                    &tags_name(&old.tag_ids),
+                    &tags_name(&request.tag_ids),
+                ),
+                describe_change("billable", yes_no(old.billable), yes_no(request.billable)),
+            ];
+            let lines = lines.into_iter().flatten().collect::<Vec<String>>();
+            changes.push((task, request, lines));
+        }
+        if changes.is_empty() {
+            println!("No tasks to change");
+            return;
+        }
+
+        println!("{} tasks will be changed:\n", changes.len());
+        for (task, _, lines) in &changes {
+            println!("{} ({})", task.description, task.time.describe());
+            for line in lines {
+                println!("{}", line);
+            }
+        }
+        println!();
+        if !self.yes && !cli.select_bool("Do you want to apply these changes?", "pass --yes") {
+            println!("No tasks changed");
+            return;
+        }
+
+        let mut applied = ChangeSet::new(api, &workspace);
+        for (task, request, _) in &changes {
+            if applied.update(task, request).await.is_none() {
+                println!("Failed to change '{}'", task.description);
+                if !applied.rollback().await {
+                    println!("Some of the changes could not be undone, please check your tasks");
+                }
+                return;
+            }
+        }
+        println!("{} tasks changed successfully", changes.len());
+    }
+}
+
+/// Delete tasks chosen by id, by filters or from a list
+#[derive(Debug, Args)]
+pub struct DeleteCommand {
+    /// The ids of the tasks, prompts for them when neither ids nor filters are given
+    ids: Vec<String>,
+    #[clap(flatten)]
+    filter: TaskFilter,
+    /// Deletes the tasks without asking for confirmation
+    #[clap(short, long)]
+    yes: bool,
+    /// Only shows the tasks that would be deleted
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl DeleteCommand {
+    pub async fn run(&self, cli: &ClockifyCLI) {
+        let api = &cli.api;
+        let workspace = cli.current_workspace().await;
+        if workspace.is_none() {
+            return;
+        }
+        let workspace = workspace.unwrap();
+        let tasks = if !self.ids.is_empty() {
+            let recent = api.get_tasks(&workspace).await.unwrap_or_default();
+            let mut tasks = Vec::new();
+            for id in &self.ids {
+                // The same id given twice is deleted once
+                if tasks.iter().any(|task: &Task| &task.id == id) {
+                    continue;
+                }
+                let task = recent.iter().find(|task| &task.id == id);
+                if task.is_none() {
+                    println!("Task '{}' not found", id);
+                    return;
+                }
+                tasks.push(task.unwrap().clone());
+            }
+            Some(tasks)
+        } else if !self.filter.is_empty() {
+            self.filter.tasks(api, &workspace).await
+        } else {
+            require_input("the tasks", "pass their ids or filters such as --since");
+            cli.select_tasks(&workspace).await
+        };
+        if tasks.is_none() {
+            return;
+        }
+        let tasks = tasks.unwrap();
+        if tasks.is_empty() {
+            println!("No tasks to delete");
+            return;
+        }
+
+        let total = tasks
+            .iter()
+            .fold(Duration::zero(), |total, task| total + task.duration());
+        println!("{} tasks will be deleted:\n", tasks.len());
+        for task in &tasks {
+            println!(
+                "{} [{}] ({})",
+                task.description,
+                format_duration(task.duration()),
+                task.time.describe()
+            );
+        }
+        println!("\nTotal: {}\n", format_duration(total));
+        if self.dry_run {
+            println!("Dry run, no tasks deleted");
+            return;
+        }
+        if !self.yes && !cli.select_bool("Do you want to delete these tasks?", "pass --yes") {
+            println!("No tasks deleted");
+            return;
+        }
+
+        let mut deleted = ChangeSet::new(api, &workspace);
+        for task in &tasks {
+            if deleted.delete(task).await.is_none() {
+                println!("Failed to delete '{}'", task.description);
+                if !deleted.rollback().await {
+                    println!("Some of the tasks could not be restored, please check your tasks");
+                }
+                return;
+            }
+        }
+        println!("{} tasks deleted successfully", tasks.len());
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Weekday};
+use clap::Parser;
+
+use crate::{
+    api::ClockifyCLI,
+    utils::{datetime, format_duration, now, paint, parse_date, split_days, week_start},
+};
+
+const RED: &str = "31";
+const DIM: &str = "2";
+
+/// Show the hours logged on each day of a month
+#[derive(Debug, Parser)]
+pub struct CalendarCommand {
+    /// The month to show (e.g. 2023-04), or any day in it (e.g. -4w)
+    #[clap(long)]
+    month: Option<String>,
+    /// The hours expected on a working day, defaults to the configured daily target
+    #[clap(long, value_name = "HOURS")]
+    target: Option<f64>,
+}
+
+/// Parses a month such as `2023-04`, or any date accepted by [`parse_date`],
+/// and returns its first day
+fn parse_month(input: &str) -> Option<NaiveDate> {
+    if let Some((year, month)) = input.trim().split_once('-') {
+        if year.len() == 4 && month.len() <= 2 {
+            return NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, 1);
+        }
+    }
+    parse_date(input)?.with_day(1)
+}
+
+impl CalendarCommand {
+    pub async fn run(&self, cli: &ClockifyCLI) {
+        let api = &cli.api;
+        let config = api.manager.config.as_ref().unwrap();
+        let first = match &self.month {
+            Some(month) => parse_month(month),
+            None => now().date().with_day(1),
+        };
+        if first.is_none() {
+            println!("Invalid month, expected e.g. 2023-04");
+            return;
+        }
+        let first = first.unwrap();
+        let next = first + Duration::days(32);
+        let next = next.with_day(1).unwrap();
+        let target = self.target.or(config.daily_target);
+        if target.is_some_and(|hours| !(0.0..=24.0).contains(&hours)) {
+            println!("The daily target must be between 0 and 24 hours");
+            return;
+        }
+        let target = target.filter(|hours| *hours > 0.0);
+        let target = target.map(|hours| Duration::minutes((hours * 60.0).round() as i64));
+
+        let workspace = cli.current_workspace().await;
+        if workspace.is_none() {
+            return;
+        }
+        let workspace = workspace.unwrap();
+        // The day before is fetched too, for the tasks running past midnight into the month
+        let from = datetime(first - Duration::days(1), NaiveTime::MIN);
+        let to = datetime(next, NaiveTime::MIN);
+        let tasks = api.get_tasks_between(&workspace, from, to).await;
+        if tasks.is_none() {
+            return;
+        }
+        let mut logged: HashMap<NaiveDate, Duration> = HashMap::new();
+        for task in tasks.unwrap() {
+            // Tasks running past midnight count on each day they ran
+            if let Some(start) = task.start() {
+                for (date, duration) in split_days((start, start + task.duration())) {
+                    *logged.entry(date).or_default() += duration;
+                }
+            }
+        }
+
+        let today = now().date();
+        let day_off = |date: NaiveDate| {
+            matches!(date.weekday(), Weekday::Sat | Weekday::Sun) || config.holidays.contains(&date)
+        };
+        let texts = first
+            .iter_days()
+            .take_while(|date| *date < next)
+            .map(|date| match logged.get(&date) {
+                Some(duration) => format_duration(*duration),
+                None => String::from("-"),
+            })
+            .collect::<Vec<String>>();
+        let width = texts.iter().map(String::len).max().unwrap_or(1);
+        let cell_width = width + 3;
+
+        let week_first = week_start(first, config.week_start.unwrap_or(Weekday::Mon));
+        let title = first.format("%B %Y").to_string();
+        println!("{:^total$}\n", title, total = cell_width * 7 + 6);
+        let header = (0..7)
+            .map(|day| {
+                let date = week_first + Duration::days(day);
+                format!("{:<cell_width$}", date.format("%a"))
+            })
+            .collect::<Vec<String>>();
+        println!("{}", header.join(" ").trim_end());
+
+        let mut below = 0;
+        let mut total = Duration::zero();
+        let mut date = week_first;
+        while date < next {
+            let mut line = Vec::new();
+            for _ in 0..7 {
+                if date < first || date >= next {
+                    line.push(" ".repeat(cell_width));
+                    date += Duration::days(1);
+                    continue;
+                }
+                let duration = logged.get(&date).copied().unwrap_or_default();
+                total += duration;
+                let text = &texts[(date - first).num_days() as usize];
+                let cell = format!("{:>2} {:>width$}", date.day(), text);
+                let missing = target.is_some_and(|target| duration < target);
+                let cell = if day_off(date) {
+                    format!("{} ", paint(&cell, DIM))
+                } else if missing && date <= today {
+                    below += 1;
+                    format!("{}!", paint(&cell, RED))
+                } else {
+                    format!("{} ", cell)
+                };
+                line.push(cell);
+                date += Duration::days(1);
+            }
+            println!("{}", line.join(" ").trim_end());
+        }
+
+        println!("\nTotal: {}", format_duration(total));
+        if let Some(target) = target {
+            println!(
+                "{} working days below the target of {} (marked with !)",
+                below,
+                format_duration(target)
+            );
+        }
+    }
+}
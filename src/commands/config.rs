@@ -1,7 +1,10 @@
-use crate::{api::{ClockifyCLI}, utils::clear_screen};
+use crate::{
+    api::ClockifyCLI,
+    utils::{clear_screen, parse_date, parse_time, parse_weekday, DurationFormat, Zone},
+};
 use clap::{Parser, Subcommand};
 
-/// Configure the authentication token
+/// Configure the authentication token and preferences
 #[derive(Debug, Parser)]
 pub struct ConfigCommand {
     #[clap(subcommand)]
@@ -15,6 +18,49 @@ pub enum ConfigSubCommand {
         /// The API key to use
         api_key: String,
     },
+    /// Shows or sets the timezone used for the times of the tasks
+    Timezone {
+        /// An IANA timezone such as Europe/Madrid, or "local" to use the system one
+        timezone: Option<String>,
+        /// Uses the timezone of your Clockify profile
+        #[clap(long, conflicts_with = "timezone")]
+        from_profile: bool,
+    },
+    /// Sets how durations are shown
+    DurationFormat {
+        #[clap(value_enum)]
+        format: DurationFormat,
+    },
+    /// Sets the first day of the week in reports
+    WeekStart {
+        /// The name of the day, e.g. monday or sun
+        day: String,
+    },
+    /// Sets how many minutes the durations in reports are rounded to, 0 to not round them
+    Rounding { minutes: u32 },
+    /// Sets the hours expected to be logged on a working day, 0 to not expect any
+    DailyTarget { hours: f64 },
+    /// Adds a day off, or removes it with --remove, lists them when no date is given
+    Holiday {
+        /// The date of the day off (e.g. 2023-12-25)
+        date: Option<String>,
+        /// Removes the day off
+        #[clap(long, requires = "date")]
+        remove: bool,
+    },
+    /// Sets the working hours used to find gaps, shows them when nothing is given
+    WorkingHours {
+        /// When the working day starts (e.g. 9:00)
+        start: Option<String>,
+        /// When the working day ends (e.g. 17:30)
+        end: Option<String>,
+        /// The working days, separated by commas (e.g. mon,tue,wed,thu,fri)
+        #[clap(long, value_delimiter = ',', value_name = "DAYS")]
+        days: Vec<String>,
+        /// The lunch break, which is not expected to be logged (e.g. 12:30-13:15), or none
+        #[clap(long)]
+        lunch: Option<String>,
+    },
 }
 
 impl ConfigCommand {
@@ -33,6 +79,198 @@ impl ConfigCommand {
                 println!("Logged in successfully");
                 api.manager.save();
             }
+            ConfigSubCommand::Timezone {
+                timezone,
+                from_profile,
+            } => {
+                let timezone = if *from_profile {
+                    let user = api.get_user().await;
+                    let timezone = user
+                        .and_then(|user| user.settings)
+                        .and_then(|settings| settings.time_zone);
+                    if timezone.is_none() {
+                        println!("Your Clockify profile has no timezone");
+                        return;
+                    }
+                    timezone
+                } else {
+                    timezone.clone()
+                };
+                if timezone.is_none() {
+                    let current = &api.manager.config.as_ref().unwrap().timezone;
+                    println!(
+                        "Timezone: {}",
+                        current.as_deref().unwrap_or("local (system timezone)")
+                    );
+                    return;
+                }
+                let timezone = timezone.unwrap();
+                if Zone::parse(&timezone).is_none() {
+                    println!("Unknown timezone '{}'", timezone);
+                    return;
+                }
+                let local = timezone.eq_ignore_ascii_case("local");
+                let config = api.manager.config.as_mut().unwrap();
+                config.timezone = if local { None } else { Some(timezone.clone()) };
+                api.manager.save();
+                println!("Timezone set to {}", timezone);
+            }
+            ConfigSubCommand::DurationFormat { format } => {
+                let config = api.manager.config.as_mut().unwrap();
+                config.duration_format = *format;
+                api.manager.save();
+                println!(
+                    "Durations will be shown as {}",
+                    match format {
+                        DurationFormat::Text => "1h 30m",
+                        DurationFormat::Clock => "1:30",
+                        DurationFormat::Decimal => "1.50",
+                    }
+                );
+            }
+            ConfigSubCommand::WeekStart { day } => {
+                let weekday = parse_weekday(day);
+                if weekday.is_none() {
+                    println!("Unknown day '{}'", day);
+                    return;
+                }
+                let config = api.manager.config.as_mut().unwrap();
+                config.week_start = weekday;
+                api.manager.save();
+                println!("Weeks will start on {}", weekday.unwrap());
+            }
+            ConfigSubCommand::Rounding { minutes } => {
+                let config = api.manager.config.as_mut().unwrap();
+                config.rounding = Some(*minutes).filter(|minutes| *minutes > 0);
+                api.manager.save();
+                match minutes {
+                    0 => println!("Durations will not be rounded"),
+                    _ => println!("Durations will be rounded to {} minutes", minutes),
+                }
+            }
+            ConfigSubCommand::DailyTarget { hours } => {
+                if !(0.0..=24.0).contains(hours) {
+                    println!("The daily target must be between 0 and 24 hours");
+                    return;
+                }
+                let target = Some(*hours).filter(|hours| *hours > 0.0);
+                let config = api.manager.config.as_mut().unwrap();
+                config.daily_target = target;
+                api.manager.save();
+                match target {
+                    Some(hours) => println!("Daily target set to {} hours", hours),
+                    None => println!("Daily target disabled"),
+                }
+            }
+            ConfigSubCommand::Holiday { date, remove } => {
+                let config = api.manager.config.as_mut().unwrap();
+                if date.is_none() {
+                    if config.holidays.is_empty() {
+                        println!("No holidays configured");
+                    }
+                    for holiday in &config.holidays {
+                        println!("{}", holiday.format("%A %Y-%m-%d"));
+                    }
+                    return;
+                }
+                let day = parse_date(date.as_ref().unwrap());
+                if day.is_none() {
+                    println!("Invalid date");
+                    return;
+                }
+                let day = day.unwrap();
+                let listed = config.holidays.contains(&day);
+                match (*remove, listed) {
+                    (true, false) => {
+                        println!("{} is not a holiday", day);
+                        return;
+                    }
+                    (false, true) => {
+                        println!("{} is already a holiday", day);
+                        return;
+                    }
+                    (true, true) => {
+                        config.holidays.retain(|holiday| *holiday != day);
+                        println!("{} is no longer a holiday", day);
+                    }
+                    (false, false) => {
+                        config.holidays.push(day);
+                        config.holidays.sort();
+                        println!("{} added as a holiday", day);
+                    }
+                }
+                api.manager.save();
+            }
+            ConfigSubCommand::WorkingHours {
+                start,
+                end,
+                days,
+                lunch,
+            } => {
+                let config = api.manager.config.as_mut().unwrap();
+                let mut hours = config.working_hours.clone().unwrap_or_default();
+                if start.is_none() && end.is_none() && days.is_empty() && lunch.is_none() {
+                    println!("Working hours: {}", hours.describe());
+                    return;
+                }
+                if let Some(start) = start {
+                    let time = parse_time(start, None);
+                    if time.is_none() {
+                        println!("Invalid start time");
+                        return;
+                    }
+                    hours.start = time.unwrap();
+                }
+                if let Some(end) = end {
+                    let time = parse_time(end, Some(hours.start));
+                    if time.is_none() {
+                        println!("Invalid end time");
+                        return;
+                    }
+                    hours.end = time.unwrap();
+                }
+                if !days.is_empty() {
+                    let weekdays = days.iter().map(|day| parse_weekday(day));
+                    let weekdays = weekdays.collect::<Option<Vec<_>>>();
+                    if weekdays.is_none() {
+                        println!("Unknown day in '{}'", days.join(","));
+                        return;
+                    }
+                    hours.days = weekdays.unwrap();
+                }
+                if let Some(lunch) = lunch {
+                    hours.lunch = if lunch.eq_ignore_ascii_case("none") {
+                        None
+                    } else {
+                        let times = lunch.split_once('-').and_then(|(from, to)| {
+                            let from = parse_time(from, None)?;
+                            Some((from, parse_time(to, Some(from))?))
+                        });
+                        if times.is_none() {
+                            println!("Invalid lunch break, expected e.g. 12:30-13:15");
+                            return;
+                        }
+                        times
+                    };
+                }
+                if hours.end <= hours.start {
+                    println!("The working day must end after it starts");
+                    return;
+                }
+                if let Some((from, to)) = hours.lunch {
+                    if to <= from || from < hours.start || to > hours.end {
+                        println!(
+                            "The lunch break must be within the working hours, from {} to {}",
+                            hours.start.format("%H:%M"),
+                            hours.end.format("%H:%M")
+                        );
+                        return;
+                    }
+                }
+                println!("Working hours set to {}", hours.describe());
+                config.working_hours = Some(hours);
+                api.manager.save();
+            }
         }
     }
 }
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, NaiveTime, SecondsFormat, Utc, Weekday};
+use clap::Parser;
+
+use crate::{
+    api::{ClockifyCLI, TaskRequest, Workspace},
+    commands::task::{fill_template, TaskDraft},
+    utils::{
+        datetime, format_duration, interactive, now, parse_date, parse_week, subtract, to_local,
+        Interval,
+    },
+};
+
+/// List the working hours with no task logged, and offer to fill them
+#[derive(Debug, Parser)]
+pub struct GapsCommand {
+    /// The day to check (e.g. today, yesterday, mon, -3d, 2023-04-03), defaults to today
+    #[clap(long, conflicts_with = "week")]
+    date: Option<String>,
+    /// The week to check, as an ISO week (e.g. 2023-W14) or any day in it (e.g. -1w)
+    #[clap(long)]
+    week: Option<String>,
+    /// Ignores the gaps shorter than this many minutes
+    #[clap(long, value_name = "MINUTES", default_value_t = 5)]
+    min: i64,
+    /// Only lists the gaps, without offering to fill them
+    #[clap(long)]
+    list: bool,
+}
+
+fn describe(gap: &Interval) -> String {
+    let (start, end) = (to_local(gap.0), to_local(gap.1));
+    format!(
+        "{} {} - {} ({})",
+        start.format("%a %Y-%m-%d"),
+        start.format("%H:%M"),
+        end.format("%H:%M"),
+        format_duration(gap.1 - gap.0)
+    )
+}
+
+/// Asks how to fill the gap and builds the task for it, `None` when it is skipped
+async fn fill(cli: &ClockifyCLI, workspace: &Workspace, gap: &Interval) -> Option<TaskRequest> {
+    let templates = cli.api.manager.get_saved_tasks();
+    let templates = templates
+        .into_iter()
+        .filter(|saved| {
+            let id = saved.workspace_id.as_ref();
+            id.is_none_or(|id| *id == workspace.id)
+        })
+        .collect::<Vec<_>>();
+    let mut options = vec![String::from("Pick a project")];
+    options.extend(
+        templates
+            .iter()
+            .map(|saved| format!("Template: {}", saved.name)),
+    );
+    options.push(String::from("Skip"));
+    let idx = cli.pick(&format!("Fill {}?", describe(gap)), options)?;
+
+    let mut request = if idx == 0 {
+        // Built like the tasks of `task add`, so the directory defaults apply to it too
+        let directory = cli.api.manager.directory.clone().unwrap_or_default();
+        let mut defaults = Vec::new();
+        if !directory.tags.is_empty() {
+            let tags = cli.api.find_tags(workspace, &directory.tags).await;
+            defaults = tags.into_iter().map(|tag| tag.id).collect();
+        }
+        let mut draft = TaskDraft::default();
+        draft.project = Some(cli.select_project(workspace).await?);
+        draft.description = Some(cli.select_text("Enter a description:").await?);
+        draft.tags = cli.select_tags(workspace, &defaults).await;
+        draft.request(&directory, gap.0, gap.1)
+    } else if idx <= templates.len() {
+        let entry = &templates[idx - 1];
+        let date = to_local(gap.0).date();
+        fill_template(cli, workspace, entry, date, HashMap::new()).await
+    } else {
+        return None;
+    };
+    request.start = gap.0.to_rfc3339_opts(SecondsFormat::Millis, true);
+    request.end = Some(gap.1.to_rfc3339_opts(SecondsFormat::Millis, true));
+    Some(request)
+}
+
+impl GapsCommand {
+    pub async fn run(&self, cli: &ClockifyCLI) {
+        let api = &cli.api;
+        let config = api.manager.config.as_ref().unwrap();
+        let hours = config.working_hours.clone().unwrap_or_default();
+        let today = now().date();
+        let days = if let Some(week) = &self.week {
+            let start = parse_week(week, config.week_start.unwrap_or(Weekday::Mon));
+            if start.is_none() {
+                println!("Invalid week, expected e.g. 2023-W14");
+                return;
+            }
+            let start = start.unwrap();
+            (0..7).map(|day| start + Duration::days(day)).collect()
+        } else {
+            let date = match &self.date {
+                Some(date) => parse_date(date),
+                None => Some(today),
+            };
+            if date.is_none() {
+                println!("Invalid date");
+                return;
+            }
+            vec![date.unwrap()]
+        };
+        let first = *days.first().unwrap();
+        let last = *days.last().unwrap();
+        let days = days
+            .into_iter()
+            .filter(|day| {
+                hours.days.contains(&day.weekday())
+                    && !config.holidays.contains(day)
+                    && *day <= today
+            })
+            .collect::<Vec<_>>();
+        if days.is_empty() {
+            println!("No working days to check, see `config working-hours`");
+            return;
+        }
+
+        let workspace = cli.current_workspace().await;
+        if workspace.is_none() {
+            return;
+        }
+        let workspace = workspace.unwrap();
+        let from = datetime(first, NaiveTime::MIN);
+        let to = datetime(last + Duration::days(1), NaiveTime::MIN);
+        let tasks = api.get_tasks_between(&workspace, from, to).await;
+        if tasks.is_none() {
+            return;
+        }
+        let busy = tasks
+            .unwrap()
+            .iter()
+            .filter_map(|task| Some((task.start()?, task.start()? + task.duration())))
+            .collect::<Vec<Interval>>();
+
+        let mut gaps = Vec::new();
+        for day in days {
+            let mut free = vec![(datetime(day, hours.start), datetime(day, hours.end))];
+            if let Some((start, end)) = hours.lunch {
+                free = subtract(free, (datetime(day, start), datetime(day, end)));
+            }
+            if day == today {
+                let limit = Utc::now();
+                free = free
+                    .into_iter()
+                    .map(|(start, end)| (start, end.min(limit)))
+                    .filter(|(start, end)| start < end)
+                    .collect();
+            }
+            for interval in &busy {
+                free = subtract(free, *interval);
+            }
+            gaps.extend(
+                free.into_iter()
+                    .filter(|(start, end)| *end - *start >= Duration::minutes(self.min)),
+            );
+        }
+
+        if gaps.is_empty() {
+            println!("No gaps found, every working hour is logged");
+            return;
+        }
+        let total = gaps.iter().map(|(start, end)| *end - *start).sum();
+        println!("Gaps in your working hours ({}):\n", hours.describe());
+        for gap in &gaps {
+            println!("{}", describe(gap));
+        }
+        println!("\nTotal: {}", format_duration(total));
+        if self.list || !interactive() {
+            return;
+        }
+
+        println!();
+        let mut filled = 0;
+        for gap in &gaps {
+            let request = fill(cli, &workspace, gap).await;
+            if request.is_none() {
+                continue;
+            }
+            let created = api.new_task(&workspace, &request.unwrap()).await;
+            if created.is_none() || !created.unwrap() {
+                println!("Failed to fill {}", describe(gap));
+                continue;
+            }
+            filled += 1;
+        }
+        println!("Filled {} of {} gaps", filled, gaps.len());
+    }
+}
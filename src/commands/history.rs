@@ -0,0 +1,29 @@
+use clap::Parser;
+
+use crate::{journal, utils::to_local};
+
+/// List the latest changes made to the tasks, which can be reverted with `undo`
+#[derive(Debug, Parser)]
+pub struct HistoryCommand {
+    /// How many changes to show
+    #[clap(short = 'n', long, default_value_t = 10)]
+    limit: usize,
+}
+
+impl HistoryCommand {
+    pub async fn run(&self) {
+        let entries = journal::load();
+        if entries.is_empty() {
+            println!("No changes recorded");
+            return;
+        }
+        for (idx, entry) in entries.iter().rev().take(self.limit).enumerate() {
+            println!(
+                "[{}] {} {}",
+                idx + 1,
+                to_local(entry.time).format("%Y-%m-%d %H:%M"),
+                entry.operation.describe()
+            );
+        }
+    }
+}
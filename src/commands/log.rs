@@ -0,0 +1,327 @@
+use chrono::{Duration, NaiveDate, NaiveTime, SecondsFormat};
+use clap::Parser;
+
+use crate::{
+    api::{ClockifyCLI, Project, TaskRequest},
+    utils::{
+        datetime, format_duration, fuzzy_filter, now, parse_date, parse_span, parse_time, to_local,
+    },
+};
+
+/// Log a task from a single line, e.g. "2h30 ProjectX #meeting standup yesterday"
+///
+/// Durations (2h30, 45m), time ranges (9-11:30), @project, #tags and #billable / #non-billable
+/// are picked from the text, as well as a date (yesterday, last friday, -3d) at its start or
+/// end. The rest becomes the description. Without @project, a word matching a project name is
+/// used.
+#[derive(Debug, Parser)]
+pub struct LogCommand {
+    /// The task to log
+    #[clap(required = true, trailing_var_arg = true)]
+    text: Vec<String>,
+    /// Creates the task without asking for confirmation
+    #[clap(short, long)]
+    yes: bool,
+}
+
+/// The parts of a task found in a log line
+#[derive(Debug, Default)]
+struct LogEntry {
+    words: Vec<String>,
+    project: Option<String>,
+    tags: Vec<String>,
+    billable: Option<bool>,
+    date: Option<NaiveDate>,
+    start: Option<NaiveTime>,
+    end: Option<NaiveTime>,
+    duration: Option<Duration>,
+}
+
+/// Parses durations such as `2h30`, `2h`, `90m` or `1h30m`
+fn parse_log_duration(word: &str) -> Option<Duration> {
+    if !word.contains(['h', 'm']) || !word.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    if word.ends_with(|c: char| c.is_ascii_digit()) {
+        return parse_span(&format!("{}m", word));
+    }
+    parse_span(word)
+}
+
+/// Parses a time written with a separator or a meridiem, so plain numbers stay in the description
+fn parse_log_time(word: &str) -> Option<NaiveTime> {
+    if !word.contains([':', 'a', 'p']) {
+        return None;
+    }
+    parse_time(word, None)
+}
+
+/// Parses time ranges such as `9-11:30` or `9:30am-1pm`
+fn parse_log_range(word: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let (start, end) = word.split_once('-')?;
+    if start.is_empty() {
+        return None;
+    }
+    Some((parse_time(start, None)?, parse_time(end, None)?))
+}
+
+fn parse_entry(text: &str) -> LogEntry {
+    let mut entry = LogEntry::default();
+    for word in text.split_whitespace() {
+        let lower = word.to_lowercase();
+        if let Some(project) = word.strip_prefix('@').filter(|name| !name.is_empty()) {
+            entry.project = Some(project.replace('_', " "));
+            continue;
+        }
+        if let Some(tag) = word.strip_prefix('#').filter(|name| !name.is_empty()) {
+            entry.tags.push(tag.replace('_', " "));
+            continue;
+        }
+        if entry.duration.is_none() {
+            if let Some(duration) = parse_log_duration(&lower) {
+                entry.duration = Some(duration);
+                continue;
+            }
+        }
+        if entry.start.is_none() {
+            if let Some((start, end)) = parse_log_range(&lower) {
+                entry.start = Some(start);
+                entry.end = Some(end);
+                continue;
+            }
+            if let Some(start) = parse_log_time(&lower) {
+                entry.start = Some(start);
+                continue;
+            }
+        }
+        entry.words.push(word.to_string());
+    }
+    entry.date = take_date(&mut entry.words);
+    entry
+}
+
+/// Takes a date from the end or the start of the description, so words such as "today" or
+/// "friday" in the middle of it are kept
+fn take_date(words: &mut Vec<String>) -> Option<NaiveDate> {
+    for len in [2, 1] {
+        if words.len() < len {
+            continue;
+        }
+        for range in [words.len() - len..words.len(), 0..len] {
+            let date = parse_date(&words[range.clone()].join(" "));
+            if date.is_some() {
+                words.drain(range);
+                return date;
+            }
+        }
+    }
+    None
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Finds the project whose name matches the word, ignoring case and punctuation
+fn find_project<'a>(projects: &'a [Project], word: &str) -> Option<&'a Project> {
+    let word = normalize(word);
+    if word.is_empty() {
+        return None;
+    }
+    projects
+        .iter()
+        .find(|project| normalize(&project.name) == word)
+}
+
+/// Finds the project named by the query, exact matches first and then a unique fuzzy one
+fn match_project<'a>(projects: &'a [Project], query: &str) -> Option<&'a Project> {
+    let exact = projects
+        .iter()
+        .find(|project| project.id == query)
+        .or_else(|| find_project(projects, query));
+    if exact.is_some() {
+        return exact;
+    }
+    let query = normalize(query);
+    if query.len() < 3 {
+        return None;
+    }
+    let mut fuzzy = projects
+        .iter()
+        .filter(|project| fuzzy_filter(&query, &project.name, &project.name, 0));
+    let found = fuzzy.next();
+    if fuzzy.next().is_some() {
+        return None;
+    }
+    found
+}
+
+impl LogCommand {
+    pub async fn run(&self, cli: &ClockifyCLI) {
+        let api = &cli.api;
+        // A --yes after the text is taken as part of it
+        let is_yes = |word: &&String| *word == "--yes" || *word == "-y";
+        let yes = self.yes || self.text.iter().any(|word| is_yes(&word));
+        let text = self.text.iter().filter(|word| !is_yes(word));
+        let mut entry = parse_entry(&text.cloned().collect::<Vec<String>>().join(" "));
+        let directory = api.manager.directory.clone().unwrap_or_default();
+
+        let workspace = cli.current_workspace().await;
+        if workspace.is_none() {
+            return;
+        }
+        let workspace = workspace.unwrap();
+        let projects = api.get_projects(&workspace).await.unwrap_or_default();
+
+        let project = match &entry.project {
+            Some(query) => {
+                let project = match_project(&projects, query);
+                if project.is_none() {
+                    println!("Project '{}' not found", query);
+                    return;
+                }
+                project.cloned()
+            }
+            None => {
+                let words = &entry.words;
+                let idx = words
+                    .iter()
+                    .position(|word| find_project(&projects, word).is_some())
+                    .or_else(|| {
+                        words
+                            .iter()
+                            .position(|word| match_project(&projects, word).is_some())
+                    });
+                match idx {
+                    Some(idx) => {
+                        let word = entry.words.remove(idx);
+                        match_project(&projects, &word).cloned()
+                    }
+                    None => cli.current_project(&workspace).await,
+                }
+            }
+        };
+        if project.is_none() {
+            println!("No project selected");
+            return;
+        }
+        let project = project.unwrap();
+
+        let workspace_tags = api.get_tags(&workspace).await.unwrap_or_default();
+        let mut tags = Vec::new();
+        for name in &entry.tags {
+            let tag = workspace_tags
+                .iter()
+                .find(|tag| tag.name.eq_ignore_ascii_case(name));
+            match tag {
+                Some(tag) => tags.push(tag.clone()),
+                None if name.eq_ignore_ascii_case("billable") => entry.billable = Some(true),
+                None if normalize(name) == "nonbillable" => entry.billable = Some(false),
+                None => {
+                    println!("Tag '{}' not found", name);
+                    return;
+                }
+            }
+        }
+        // #billable and #non-billable are not tags, so the directory tags still apply with them
+        if tags.is_empty() && !directory.tags.is_empty() {
+            tags = api.find_tags(&workspace, &directory.tags).await;
+        }
+
+        let date = entry.date.unwrap_or(now().date());
+        let (start, end) = match (entry.start, entry.end, entry.duration) {
+            (Some(start), Some(end), _) => {
+                let end_date = if end < start {
+                    date + Duration::days(1)
+                } else {
+                    date
+                };
+                (datetime(date, start), datetime(end_date, end))
+            }
+            (Some(start), None, Some(duration)) => {
+                let start = datetime(date, start);
+                (start, start + duration)
+            }
+            (None, None, Some(duration)) => {
+                let end = datetime(date, now().time());
+                (end - duration, end)
+            }
+            _ => {
+                println!("Please give a duration (e.g. 2h30) or a time range (e.g. 9-11:30)");
+                return;
+            }
+        };
+        if end <= start {
+            println!("The task must end after it starts");
+            return;
+        }
+
+        let description = directory.describe(&entry.words.join(" "));
+        let mut request = TaskRequest {
+            description: description.clone(),
+            start: start.to_rfc3339_opts(SecondsFormat::Millis, true),
+            end: Some(end.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            billable: entry.billable.or(directory.billable).unwrap_or(true),
+            project_id: Some(project.id.clone()),
+            task_id: None,
+            tag_ids: tags.iter().map(|tag| tag.id.clone()).collect(),
+            custom_fields: Vec::new(),
+        };
+
+        let mut summary = format!("Description: {}\nProject: {}\n", description, project.name);
+        if !tags.is_empty() {
+            let names = tags.iter().map(|tag| tag.name.as_str());
+            let names = names.collect::<Vec<&str>>().join(", ");
+            summary.push_str(&format!("Tags: {}\n", names));
+        }
+        summary.push_str(&format!(
+            "Billable: {}\nTime: {} - {} [{}]\n",
+            if request.billable { "Yes" } else { "No" },
+            to_local(start).format("%Y-%m-%d %H:%M"),
+            to_local(end).format("%Y-%m-%d %H:%M"),
+            format_duration(end - start)
+        ));
+
+        let question = format!("{}\nDo you want to create this task?", summary);
+        if yes {
+            println!("{}", summary);
+        } else if !cli.select_bool(&question, "pass --yes") {
+            println!("Task not created");
+            return;
+        }
+        if !cli.resolve_overlaps(&workspace, &mut request).await {
+            println!("Task not created");
+            return;
+        }
+        let task = api.new_task(&workspace, &request).await;
+        if task.is_none() || !task.as_ref().unwrap() {
+            println!("Failed to create task");
+            println!("Please report this issue!");
+            return;
+        }
+        println!("Task created successfully");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_dates_only_from_the_ends() {
+        let entry = parse_entry("2h30 #meeting plan today's work with client yesterday");
+        assert_eq!(entry.date, parse_date("yesterday"));
+        assert_eq!(entry.words.join(" "), "plan today's work with client");
+
+        let entry = parse_entry("last friday review sat notes and today plans 1h");
+        assert_eq!(entry.date, parse_date("last friday"));
+        assert_eq!(entry.words.join(" "), "review sat notes and today plans");
+
+        let entry = parse_entry("9-11:30 call about friday release");
+        assert_eq!(entry.date, None);
+        assert_eq!(entry.words.join(" "), "call about friday release");
+    }
+}
@@ -2,7 +2,7 @@ use clap::Parser;
 
 use crate::{
     api::ClockifyCLI,
-    utils::{clear_screen, parse_duration},
+    utils::{clear_screen, display_duration},
 };
 
 /// List all clockify tags which are available to the user
@@ -12,7 +12,11 @@ pub struct ProjectsCommand;
 impl ProjectsCommand {
     pub async fn run(&self, cli: &ClockifyCLI) {
         let api = &cli.api;
-        let workspace = cli.select_workspace().await.unwrap();
+        let workspace = cli.current_workspace().await;
+        if workspace.is_none() {
+            return;
+        }
+        let workspace = workspace.unwrap();
         let projects = api.get_projects(&workspace).await;
         if projects.is_none() {
             println!("No projects found");
@@ -21,7 +25,7 @@ impl ProjectsCommand {
         clear_screen();
         println!("Registered projects:\n");
         for (idx, project) in projects.unwrap().into_iter().enumerate() {
-            let tracked = parse_duration(&project.duration[2..]);
+            let tracked = display_duration(Some(&project.duration));
             println!("[{}] {} [{}]", idx + 1, project.name, tracked);
         }
     }
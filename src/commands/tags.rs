@@ -9,7 +9,11 @@ pub struct TagsCommand;
 impl TagsCommand {
     pub async fn run(&self, cli: &ClockifyCLI) {
         let api = &cli.api;
-        let workspace = cli.select_workspace().await.unwrap();
+        let workspace = cli.current_workspace().await;
+        if workspace.is_none() {
+            return;
+        }
+        let workspace = workspace.unwrap();
         let tags = api.get_tags(&workspace).await;
         if tags.is_none() {
             println!("No tags found");
@@ -1,10 +1,16 @@
 use crate::{
-    api::{ClockifyCLI, TaskRequest},
-    utils::{clear_screen, date, parse_duration, read, cursor, datetime}, cfg::SavedTask,
+    api::{ClockifyCLI, Project, Tag, Task, TaskRequest, Workspace},
+    cfg::{DirectoryConfig, SavedTask, TemplateAnchor},
+    changes::ChangeSet,
+    commands::bulk::{BulkEditCommand, DeleteCommand},
+    utils::{
+        clear_screen, datetime, display_duration, expand, format_duration, git_branch, interactive,
+        now, parse_date, parse_end_span, parse_time, placeholders, require_input, to_local,
+    },
 };
-use chrono::SecondsFormat;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, SecondsFormat, Utc};
 use clap::{Parser, Subcommand};
-use inquire::{DateSelect};
+use std::collections::HashMap;
 
 #[derive(Debug, Parser)]
 pub struct TaskCommand {
@@ -15,47 +21,542 @@ pub struct TaskCommand {
 #[derive(Debug, Subcommand)]
 pub enum AddSubCommand {
     /// Create a new task
-    Add,
-    /// Delete a task
-    Delete,
+    Add {
+        /// The description of the task
+        #[clap(long)]
+        description: Option<String>,
+        /// The start time (e.g. 9:30, 930, 9:30am, now, -15m)
+        #[clap(long)]
+        start: Option<String>,
+        /// The end time, or the duration of the task (e.g. 17:30, now, 1h30m)
+        #[clap(long)]
+        end: Option<String>,
+        /// The date of the task (e.g. today, yesterday, mon, last friday, -3d, 2023-04-03)
+        #[clap(long)]
+        date: Option<String>,
+        /// The date the task ends, when it is not the same day it starts
+        #[clap(long)]
+        end_date: Option<String>,
+        /// The workspace id or name
+        #[clap(long)]
+        workspace: Option<String>,
+        /// The project id or name
+        #[clap(long)]
+        project: Option<String>,
+        /// Adds a tag to the task
+        #[clap(long = "tag", value_name = "NAME")]
+        tags: Vec<String>,
+        /// Creates the task without showing the review when nothing else needs to be asked
+        #[clap(short, long)]
+        yes: bool,
+    },
+    Delete(DeleteCommand),
     /// List all registered tasks
     List,
+    /// Continue a previous task, starting a timer or creating it for the given times
+    Continue {
+        /// The id of the task to continue, prompts for it when not given
+        id: Option<String>,
+        /// Continues the latest task
+        #[clap(long, conflicts_with = "id")]
+        last: bool,
+        /// The start time, starts a timer from now when not given
+        #[clap(long)]
+        start: Option<String>,
+        /// The end time, or the duration of the task, starts a timer when not given
+        #[clap(long, requires = "start")]
+        end: Option<String>,
+        /// The date of the task (e.g. today, yesterday, mon, last friday, -3d, 2023-04-03)
+        #[clap(long, requires = "start")]
+        date: Option<String>,
+    },
+    /// Split a task in two at the given time, each part can be changed afterwards
+    Split {
+        /// The id of the task to split
+        id: String,
+        /// The time the first part ends and the second one starts (e.g. 11:30)
+        #[clap(long)]
+        at: String,
+    },
+    /// Merge several tasks into one running from the first start to the last end
+    Merge {
+        /// The ids of the tasks to merge
+        #[clap(required = true, num_args = 2..)]
+        ids: Vec<String>,
+        /// The id of the task whose details are kept when they differ
+        #[clap(long)]
+        keep: Option<String>,
+    },
+    BulkEdit(BulkEditCommand),
+    /// Copy a task to another day, keeping its times
+    Duplicate {
+        /// The id of the task to copy
+        id: String,
+        /// The date to copy the task to (e.g. today, tomorrow, mon, +1w, 2023-04-03)
+        #[clap(long)]
+        date: String,
+    },
     /// Creates a new task from a saved template
-    Saved,
+    Saved {
+        /// The name of the template, applies it without prompting when given
+        name: Option<String>,
+        /// The date of the task (e.g. today, yesterday, mon, last friday, -3d, 2023-04-03)
+        #[clap(long)]
+        date: Option<String>,
+        /// Overrides the description of the template
+        #[clap(long)]
+        description: Option<String>,
+        /// The workspace id or name, defaults to the workspace of the template
+        #[clap(long)]
+        workspace: Option<String>,
+        /// Sets a template variable, used to fill `{placeholders}` in the description and tags
+        #[clap(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+    },
+}
+
+/// The steps of the `task add` wizard, in the order they are asked
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Step {
+    Project,
+    Tags,
+    Description,
+    Date,
+    Start,
+    End,
+    Review,
+}
+
+const STEPS: [Step; 6] = [
+    Step::Project,
+    Step::Tags,
+    Step::Description,
+    Step::Date,
+    Step::Start,
+    Step::End,
+];
+
+impl Step {
+    fn name(&self) -> &'static str {
+        match self {
+            Step::Project => "project",
+            Step::Tags => "tags",
+            Step::Description => "description",
+            Step::Date => "date",
+            Step::Start => "start time",
+            Step::End => "end time",
+            Step::Review => "review",
+        }
+    }
+
+    /// The flag answering the step without prompting
+    fn flag(&self) -> &'static str {
+        match self {
+            Step::Project => "--project",
+            Step::Tags => "--tag",
+            Step::Description => "--description",
+            Step::Date => "--date",
+            Step::Start => "--start",
+            Step::End => "--end",
+            Step::Review => "--yes",
+        }
+    }
+}
+
+/// The answers given so far in the `task add` wizard
+#[derive(Default)]
+pub(crate) struct TaskDraft {
+    pub(crate) project: Option<Project>,
+    pub(crate) tags: Option<Vec<Tag>>,
+    pub(crate) description: Option<String>,
+    date: Option<NaiveDate>,
+    start: Option<NaiveTime>,
+    end: Option<NaiveTime>,
+    /// Set when the end was given as a duration, so the end moves along with the start
+    span: Option<Duration>,
+    /// Only set when given explicitly, otherwise a task ending before it starts ends the next day
+    end_date: Option<NaiveDate>,
+}
+
+impl TaskDraft {
+    /// The first step without an answer, or the review once everything is answered
+    fn next_step(&self, skip_date: bool) -> Step {
+        let missing = STEPS.into_iter().find(|step| match step {
+            Step::Project => self.project.is_none(),
+            Step::Tags => self.tags.is_none(),
+            Step::Description => self.description.is_none(),
+            Step::Date => self.date.is_none() && !skip_date,
+            Step::Start => self.start.is_none(),
+            Step::End => self.end.is_none() && self.span.is_none(),
+            Step::Review => false,
+        });
+        missing.unwrap_or(Step::Review)
+    }
+
+    fn set_start(&mut self, start: NaiveTime) {
+        self.start = Some(start);
+        if let Some(span) = self.span {
+            self.end = Some(start + span);
+        }
+    }
+
+    fn tag_ids(&self) -> Vec<String> {
+        let tags = self.tags.as_deref().unwrap_or_default();
+        tags.iter().map(|tag| tag.id.clone()).collect()
+    }
+
+    /// The request creating the task from `start` to `end`, with the directory defaults applied
+    pub(crate) fn request(
+        &self,
+        directory: &DirectoryConfig,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> TaskRequest {
+        TaskRequest {
+            description: directory.describe(self.description.as_deref().unwrap_or_default()),
+            start: start.to_rfc3339_opts(SecondsFormat::Millis, true),
+            end: Some(end.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            billable: directory.billable.unwrap_or(true),
+            project_id: self.project.as_ref().map(|project| project.id.clone()),
+            task_id: None,
+            tag_ids: self.tag_ids(),
+            custom_fields: Vec::new(),
+        }
+    }
+
+    /// The start and end of the task, when it ends after it starts
+    fn interval(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let (start, end) = (self.start?, self.end?);
+        let date = self.date.unwrap_or(now().date());
+        let end_date = match self.end_date {
+            Some(end_date) => end_date,
+            None if end < start => date + Duration::days(1),
+            None => date,
+        };
+        let (start, end) = (datetime(date, start), datetime(end_date, end));
+        if end <= start {
+            return None;
+        }
+        Some((start, end))
+    }
+
+    fn summary(&self, workspace: &Workspace, directory: &DirectoryConfig) -> String {
+        let tags = self.tags.as_deref().unwrap_or_default();
+        let tags = tags.iter().map(|tag| tag.name.as_str());
+        let mut summary = format!(
+            "Workspace: {}\nProject: {}\nTags: {}\nDescription: {}\n",
+            workspace.name,
+            self.project.as_ref().map_or("", |project| &project.name),
+            tags.collect::<Vec<&str>>().join(", "),
+            directory.describe(self.description.as_deref().unwrap_or_default())
+        );
+        match self.interval() {
+            Some((start, end)) => summary.push_str(&format!(
+                "Time: {} - {} [{}]\n",
+                to_local(start).format("%A %Y-%m-%d %H:%M"),
+                to_local(end).format("%A %Y-%m-%d %H:%M"),
+                format_duration(end - start)
+            )),
+            None => summary.push_str(&format!(
+                "Date: {}\nTime: {} - {}\n",
+                self.date.unwrap_or(now().date()).format("%A %Y-%m-%d"),
+                self.start
+                    .map_or(String::new(), |start| start.format("%H:%M").to_string()),
+                self.end
+                    .map_or(String::new(), |end| end.format("%H:%M").to_string())
+            )),
+        }
+        summary
+    }
+}
+
+/// Asks for the answers missing from the draft, going back a step when one is cancelled, and
+/// then shows the review unless `skip` is set. Returns `false` when the task is not created.
+async fn ask_draft(
+    cli: &ClockifyCLI,
+    workspace: &Workspace,
+    directory: &DirectoryConfig,
+    draft: &mut TaskDraft,
+    skip: bool,
+) -> bool {
+    let mut history: Vec<Step> = Vec::new();
+    let mut step = draft.next_step(skip);
+    loop {
+        if step == Step::Review && skip {
+            if draft.interval().is_some() {
+                return true;
+            }
+            if !interactive() {
+                println!("The task must end after it starts");
+                return false;
+            }
+        }
+        if step != Step::Review {
+            let hint = format!("pass {}", step.flag());
+            require_input(&format!("the {}", step.name()), &hint);
+        }
+        let answered = match step {
+            Step::Project => {
+                let project = cli.select_project(workspace).await;
+                project.map(|project| draft.project = Some(project))
+            }
+            Step::Tags => {
+                let selected = draft.tag_ids();
+                let tags = cli.select_tags(workspace, &selected).await;
+                tags.map(|tags| draft.tags = Some(tags))
+            }
+            Step::Description => {
+                let text = cli
+                    .select_text_opt("Enter a description:", draft.description.as_deref())
+                    .await;
+                text.map(|text| draft.description = Some(text))
+            }
+            Step::Date => {
+                let date = cli.select_date(draft.date.unwrap_or(now().date()));
+                date.map(|date| draft.date = Some(date))
+            }
+            Step::Start => {
+                let start = cli.select_time(None, draft.start).await;
+                start.map(|start| draft.set_start(start))
+            }
+            Step::End => {
+                let start = draft.start.unwrap();
+                let end = cli.select_end(start, draft.end).await;
+                end.map(|(end, span)| {
+                    draft.end = Some(end);
+                    draft.span = span;
+                    draft.end_date = None;
+                })
+            }
+            Step::Review => {
+                clear_screen();
+                println!("{}", draft.summary(workspace, directory));
+                let valid = draft.interval().is_some();
+                let mut actions = Vec::new();
+                if valid {
+                    actions.push((String::from("Submit"), None));
+                } else {
+                    println!("The task must end after it starts\n");
+                }
+                for step in STEPS {
+                    actions.push((format!("Edit the {}", step.name()), Some(step)));
+                }
+                let names = actions.iter().map(|(name, _)| name.clone());
+                let action = cli.pick("What do you want to do?", names.collect());
+                match action.map(|idx| actions[idx].1) {
+                    Some(None) => return true,
+                    Some(Some(edit)) => {
+                        history.push(Step::Review);
+                        step = edit;
+                        continue;
+                    }
+                    None => None,
+                }
+            }
+        };
+        if answered.is_none() {
+            match history.pop() {
+                Some(previous) => step = previous,
+                None => return false,
+            }
+            continue;
+        }
+        history.push(step);
+        step = draft.next_step(skip);
+    }
+}
+
+/// Lets the user change the description, project and tags of a request
+async fn edit_request(cli: &ClockifyCLI, workspace: &Workspace, request: &mut TaskRequest) {
+    let description = cli
+        .select_text_opt("Enter a description:", Some(&request.description))
+        .await;
+    request.description = description.unwrap_or(request.description.clone());
+    if let Some(project) = cli.select_project(workspace).await {
+        request.project_id = Some(project.id);
+        request.task_id = None;
+    }
+    if let Some(tags) = cli.select_tags(workspace, &request.tag_ids).await {
+        request.tag_ids = tags.into_iter().map(|tag| tag.id).collect();
+    }
+}
+
+/// Whether two tasks have the same description, project, task, tags and billable flag
+fn same_details(task: &Task, other: &Task) -> bool {
+    let tags = |task: &Task| {
+        let mut tags = task.tag_ids.clone().unwrap_or_default();
+        tags.sort();
+        tags
+    };
+    task.description == other.description
+        && task.project_id == other.project_id
+        && task.task_id == other.task_id
+        && task.billable == other.billable
+        && tags(task) == tags(other)
+}
+
+/// Fills the `{placeholders}` of a template, resolves its extra tags and applies the directory
+/// defaults, asking for the variables missing from `vars`. The times of the returned request
+/// are left as they are.
+pub(crate) async fn fill_template(
+    cli: &ClockifyCLI,
+    workspace: &Workspace,
+    entry: &SavedTask,
+    date: NaiveDate,
+    vars: HashMap<String, String>,
+) -> TaskRequest {
+    let api = &cli.api;
+    let mut saved = entry.task.clone();
+    let mut values = HashMap::new();
+    values.insert("date".to_string(), date.format("%Y-%m-%d").to_string());
+    values.insert("weekday".to_string(), date.format("%A").to_string());
+    values.insert("time".to_string(), now().format("%H:%M").to_string());
+    if let Some(branch) = git_branch() {
+        values.insert("branch".to_string(), branch);
+    }
+    values.extend(vars);
+    let mut names = placeholders(&saved.description);
+    names.extend(entry.tags.iter().flat_map(|tag| placeholders(tag)));
+    for name in names {
+        if values.contains_key(&name) {
+            continue;
+        }
+        let hint = format!("pass --var {}=VALUE", name);
+        require_input(&format!("{{{}}}", name), &hint);
+        let value = cli
+            .select_text(&format!("Enter a value for {{{}}}", name))
+            .await;
+        values.insert(name, value.unwrap_or_default());
+    }
+    let directory = api.manager.directory.clone().unwrap_or_default();
+    saved.description = directory.describe(&expand(&saved.description, &values));
+    if let Some(billable) = directory.billable {
+        saved.billable = billable;
+    }
+
+    if !entry.tags.is_empty() {
+        let tags = api.get_tags(workspace).await.unwrap_or_default();
+        for name in entry.tags.iter().map(|tag| expand(tag, &values)) {
+            let tag = tags.iter().find(|tag| tag.name.eq_ignore_ascii_case(&name));
+            if tag.is_none() {
+                println!("Tag '{}' not found, skipping it", name);
+                continue;
+            }
+            let id = &tag.unwrap().id;
+            if !saved.tag_ids.contains(id) {
+                saved.tag_ids.push(id.clone());
+            }
+        }
+    }
+    saved
 }
 
 impl TaskCommand {
     pub async fn run(&self, cli: &mut ClockifyCLI) {
         let api = &cli.api;
         match &self.command {
-            AddSubCommand::Add => {
-                let workspace = cli.select_workspace().await.unwrap();
-                let project = cli.select_project(&workspace).await;
-                let tags = cli.select_tags(&workspace).await;
-                let description = ClockifyCLI::select_text("Enter a description").await;
-                let start = ClockifyCLI::select_time(None).await;
-                let end = ClockifyCLI::select_time(start).await;
-                let mut request = TaskRequest {
-                    description: description.unwrap(),
-                    start: date(start.unwrap()).to_rfc3339_opts(SecondsFormat::Millis, true),
-                    end: date(end.unwrap()).to_rfc3339_opts(SecondsFormat::Millis, true),
-                    billable: true,
-                    project_id: project.unwrap().id,
-                    task_id: None,
-                    tag_ids: tags.unwrap().iter().map(|tag| tag.id.clone()).collect(),
-                    custom_fields: Vec::new(),
+            AddSubCommand::Add {
+                description,
+                start,
+                end,
+                date: task_date,
+                end_date,
+                workspace,
+                project,
+                tags,
+                yes,
+            } => {
+                let workspace = match workspace {
+                    Some(workspace) => api.find_workspace(workspace).await,
+                    None => cli.current_workspace().await,
+                };
+                if workspace.is_none() {
+                    println!("No workspace selected");
+                    return;
+                }
+                let workspace = workspace.unwrap();
+                let directory = cli.api.manager.directory.clone().unwrap_or_default();
+                let mut draft = TaskDraft {
+                    description: description.clone(),
+                    ..Default::default()
+                };
+                if let Some(project) = project {
+                    draft.project = api.find_project(&workspace, project).await;
+                    if draft.project.is_none() {
+                        println!("Project '{}' not found", project);
+                        return;
+                    }
+                } else if let Some(project) = &directory.project {
+                    // A stale directory project is asked for in the project step instead
+                    draft.project = api.find_project(&workspace, project).await;
+                    if draft.project.is_none() {
+                        println!(
+                            "Project '{}' from {} not found, choose one instead",
+                            project,
+                            DirectoryConfig::FILE_NAME
+                        );
+                    }
+                }
+                let tags = if tags.is_empty() {
+                    &directory.tags
+                } else {
+                    tags
                 };
+                if !tags.is_empty() {
+                    draft.tags = Some(api.find_tags(&workspace, tags).await);
+                } else if !interactive() {
+                    draft.tags = Some(Vec::new());
+                }
+                let skip = *yes || !interactive();
+                // A duration is only turned into an end time once the start is known
+                if let Some(end) = end {
+                    draft.span = parse_end_span(end);
+                    if draft.span.is_none() {
+                        draft.end = parse_time(end, None);
+                        if draft.end.is_none() {
+                            println!("Invalid end time");
+                            return;
+                        }
+                    }
+                }
+                if let Some(start) = start {
+                    let start = parse_time(start, None);
+                    if start.is_none() {
+                        println!("Invalid start time");
+                        return;
+                    }
+                    draft.set_start(start.unwrap());
+                }
+                if let Some(task_date) = task_date {
+                    draft.date = parse_date(task_date);
+                    if draft.date.is_none() {
+                        println!("Invalid date");
+                        return;
+                    }
+                }
+                if let Some(end_date) = end_date {
+                    draft.end_date = parse_date(end_date);
+                    if draft.end_date.is_none() {
+                        println!("Invalid end date");
+                        return;
+                    }
+                }
 
-                let custom_date = ClockifyCLI::select_bool("Do you want to change the date of the task?");
+                if !ask_draft(cli, &workspace, &directory, &mut draft, skip).await {
+                    println!("Task not created");
+                    return;
+                }
 
-                if custom_date {
-                    let date = DateSelect::new("Select a date:").prompt().unwrap();
-                    request.start = datetime(date, start.unwrap()).to_rfc3339_opts(SecondsFormat::Millis, true);
-                    request.end = datetime(date, end.unwrap()).to_rfc3339_opts(SecondsFormat::Millis, true);
-                } else {
-                    request.start = date(start.unwrap()).to_rfc3339_opts(SecondsFormat::Millis, true);
-                    request.end = date(end.unwrap()).to_rfc3339_opts(SecondsFormat::Millis, true);
+                let (start_at, end_at) = draft.interval().unwrap();
+                let mut request = draft.request(&directory, start_at, end_at);
+                if !cli.resolve_overlaps(&workspace, &mut request).await {
+                    println!("Task not created");
+                    return;
                 }
+                // Trimming or shifting the task may have moved it
+                let (start, end) = request.times().unwrap();
+                let (start, end) = (to_local(start).time(), to_local(end.unwrap()).time());
+
                 let task = api.new_task(&workspace, &request).await;
                 if task.is_none() || !task.as_ref().unwrap() {
                     clear_screen();
@@ -63,16 +564,24 @@ impl TaskCommand {
                     println!("Please report this issue!");
                     return;
                 }
-                let save = ClockifyCLI::select_bool("Do you want to save this task as a template?");
-                if save {
-                    let alias = ClockifyCLI::select_text("Enter a name for this template").await;
+                // Saving a template is optional, so it is only offered when prompts can be answered
+                let question = "Do you want to save this task as a template?";
+                let save = interactive() && cli.select_bool(question, "");
+                let alias = match save {
+                    true => cli.select_text("Enter a name for this template").await,
+                    false => None,
+                };
+                if let Some(alias) = alias {
                     let api = &mut cli.api;
                     let mgr = &mut api.manager;
                     let saved = SavedTask {
                         task: request.clone(),
-                        name: alias.unwrap(),
-                        start: start.unwrap(),
-                        end: end.unwrap()
+                        name: alias,
+                        start,
+                        end,
+                        workspace_id: Some(workspace.id.clone()),
+                        relative: None,
+                        tags: Vec::new(),
                     };
                     mgr.save_task(saved);
                     mgr.save();
@@ -81,18 +590,13 @@ impl TaskCommand {
                 println!("Task created successfully");
                 println!("Thank you for using Clockify CLI <3!");
             }
-            AddSubCommand::Delete => {
-                let workspace = cli.select_workspace().await.unwrap();
-                let task = cli.select_task(&workspace).await.unwrap();
-                let task = api.delete_task(&workspace, &task).await;
-                if task.is_none() || !task.as_ref().unwrap() {
-                    println!("Failed to delete task");
+            AddSubCommand::Delete(delete) => delete.run(cli).await,
+            AddSubCommand::List => {
+                let workspace = cli.current_workspace().await;
+                if workspace.is_none() {
                     return;
                 }
-                println!("Task deleted successfully");
-            }
-            AddSubCommand::List => {
-                let workspace = cli.select_workspace().await.unwrap();
+                let workspace = workspace.unwrap();
                 let tasks = api.get_tasks(&workspace).await;
                 if tasks.is_none() {
                     println!("No tasks found");
@@ -102,45 +606,417 @@ impl TaskCommand {
                 println!("Registered tasks:\n");
                 for (idx, task) in tasks.unwrap().into_iter().enumerate() {
                     println!(
-                        "[{}] {} [{}]",
+                        "[{}] {} [{}] ({}) {}",
                         idx + 1,
                         task.description,
-                        parse_duration(&task.time.duration[2..])
+                        display_duration(task.time.duration.as_deref()),
+                        task.time.describe(),
+                        task.id
                     );
                 }
             }
-            AddSubCommand::Saved => {
-                let cfg = &cli.api.manager.config;
-                let saved = &cfg.as_ref().unwrap().saved_tasks;
-                if saved.is_empty() {
-                    println!("No saved templates found");
+            AddSubCommand::Continue {
+                id,
+                last,
+                start,
+                end,
+                date: task_date,
+            } => {
+                let workspace = cli.current_workspace().await;
+                if workspace.is_none() {
                     return;
                 }
-                clear_screen();
-                println!("Select a saved template to create:\n");
-                for (idx, saved) in saved.iter().enumerate() {
-                    println!("[{}] {}", idx + 1, saved.name);
+                let workspace = workspace.unwrap();
+                let task = if *last {
+                    let tasks = api.get_tasks(&workspace).await.unwrap_or_default();
+                    let task = tasks.into_iter().next();
+                    if task.is_none() {
+                        println!("No tasks found");
+                    }
+                    task
+                } else {
+                    cli.choose_task(&workspace, id.as_deref()).await
+                };
+                if task.is_none() {
+                    return;
                 }
-                cursor();
-                let idx = read::<usize>();
-                if idx.is_none() || idx.unwrap() > saved.len() {
-                    println!("Invalid index");
+                let task = task.unwrap();
+
+                let date = match task_date {
+                    Some(task_date) => parse_date(task_date),
+                    None => Some(now().date()),
+                };
+                if date.is_none() {
+                    println!("Invalid date");
                     return;
                 }
-                let entry = &saved[idx.unwrap() - 1];
-                let mut saved = entry.task.clone();
-                let workspace = cli.select_workspace().await.unwrap();
-                let name = ClockifyCLI::select_text_opt("Do you want to change the description?", Some(&saved.description)).await;
-                saved.description = name.unwrap_or(saved.description);
-                let custom_date = ClockifyCLI::select_bool("Do you want to change the date of the task?");
-
-                if custom_date {
-                    let date = DateSelect::new("Select a date:").prompt().unwrap();
-                    saved.start = datetime(date, entry.start).to_rfc3339_opts(SecondsFormat::Millis, true);
-                    saved.end = datetime(date, entry.end).to_rfc3339_opts(SecondsFormat::Millis, true);
+                let date = date.unwrap();
+                let start_time = match start {
+                    Some(start) => parse_time(start, None),
+                    None => Some(now().time()),
+                };
+                if start_time.is_none() {
+                    println!("Invalid start time");
+                    return;
+                }
+                let start_time = start_time.unwrap();
+                let start_at = datetime(date, start_time);
+                let end_at = match end {
+                    Some(end) => {
+                        let end = parse_time(end, Some(start_time));
+                        if end.is_none() {
+                            println!("Invalid end time");
+                            return;
+                        }
+                        let end = end.unwrap();
+                        let end_date = if end < start_time {
+                            date + Duration::days(1)
+                        } else {
+                            date
+                        };
+                        Some(datetime(end_date, end))
+                    }
+                    None => None,
+                };
+                if end_at.is_some_and(|end_at| end_at <= start_at) {
+                    println!("The task must end after it starts");
+                    return;
+                }
+
+                let mut request = task.request(start_at, end_at);
+                if !cli.resolve_overlaps(&workspace, &mut request).await {
+                    println!("Task not created");
+                    return;
+                }
+                let (start_at, end_at) = request.times().unwrap();
+                let created = match end_at {
+                    Some(_) => api.new_task(&workspace, &request).await,
+                    None => api.start_timer(&workspace, &request).await,
+                };
+                if created.is_none() || !created.unwrap() {
+                    println!("Failed to continue task");
+                    return;
+                }
+                match end_at {
+                    Some(end_at) => println!(
+                        "Task '{}' created ({} - {})",
+                        task.description,
+                        to_local(start_at).format("%Y-%m-%d %H:%M"),
+                        to_local(end_at).format("%Y-%m-%d %H:%M")
+                    ),
+                    None => println!(
+                        "Timer started for '{}' at {}",
+                        task.description,
+                        to_local(start_at).format("%H:%M")
+                    ),
+                }
+            }
+            AddSubCommand::Split { id, at } => {
+                let workspace = cli.current_workspace().await;
+                if workspace.is_none() {
+                    return;
+                }
+                let workspace = workspace.unwrap();
+                let task = cli.choose_task(&workspace, Some(id)).await;
+                if task.is_none() {
+                    return;
+                }
+                let task = task.unwrap();
+                let interval = task.interval();
+                if interval.is_none() {
+                    println!("A running task cannot be split");
+                    return;
+                }
+                let (start, end) = interval.unwrap();
+                let time = parse_time(at, None);
+                if time.is_none() {
+                    println!("Invalid time");
+                    return;
+                }
+                let mut split_at = datetime(to_local(start).date(), time.unwrap());
+                if split_at <= start {
+                    split_at = datetime(to_local(start).date() + Duration::days(1), time.unwrap());
+                }
+                if split_at >= end {
+                    println!(
+                        "The task runs from {}, the split time must be inside it",
+                        task.time.describe()
+                    );
+                    return;
+                }
+
+                let mut parts = [
+                    ("first", task.request(start, Some(split_at))),
+                    ("second", task.request(split_at, Some(end))),
+                ];
+                for (name, request) in parts.iter_mut() {
+                    let question = format!("Do you want to change the {} part?", name);
+                    if interactive() && cli.select_bool(&question, "") {
+                        edit_request(cli, &workspace, request).await;
+                    }
+                }
+
+                let mut changes = ChangeSet::new(api, &workspace);
+                let mut split = changes.update(&task, &parts[0].1).await.is_some();
+                split = split && changes.create(&parts[1].1).await.is_some();
+                if !split {
+                    println!("Failed to split task");
+                    if !changes.rollback().await {
+                        println!(
+                            "Some of the changes could not be undone, please check your tasks"
+                        );
+                    }
+                    return;
+                }
+                println!(
+                    "Task split into {} - {} and {} - {}",
+                    to_local(start).format("%H:%M"),
+                    to_local(split_at).format("%H:%M"),
+                    to_local(split_at).format("%H:%M"),
+                    to_local(end).format("%H:%M")
+                );
+            }
+            AddSubCommand::Merge { ids, keep } => {
+                let workspace = cli.current_workspace().await;
+                if workspace.is_none() {
+                    return;
+                }
+                let workspace = workspace.unwrap();
+                let recent = api.get_tasks(&workspace).await.unwrap_or_default();
+                let mut tasks = Vec::new();
+                for id in ids {
+                    let task = recent.iter().find(|task| &task.id == id);
+                    if task.is_none() {
+                        println!("Task '{}' not found", id);
+                        return;
+                    }
+                    let task = task.unwrap();
+                    if task.interval().is_none() {
+                        println!("The running task '{}' cannot be merged", task.description);
+                        return;
+                    }
+                    if !tasks.iter().any(|other: &Task| other.id == task.id) {
+                        tasks.push(task.clone());
+                    }
+                }
+                if tasks.len() < 2 {
+                    println!("At least two different tasks are needed to merge");
+                    return;
+                }
+                tasks.sort_by_key(|task| task.interval().unwrap().0);
+
+                let kept = match keep {
+                    Some(keep) => {
+                        let kept = tasks.iter().position(|task| &task.id == keep);
+                        if kept.is_none() {
+                            println!("The task '{}' to keep is not one of the merged tasks", keep);
+                        }
+                        kept
+                    }
+                    None if tasks.iter().all(|task| same_details(task, &tasks[0])) => Some(0),
+                    None => {
+                        require_input("which details to keep", "pass --keep <id>");
+                        let names = tasks
+                            .iter()
+                            .map(|task| format!("{} ({})", task.description, task.time.describe()));
+                        cli.pick(
+                            "The tasks have different details, which ones do you want to keep?",
+                            names.collect(),
+                        )
+                    }
+                };
+                if kept.is_none() {
+                    println!("Tasks not merged");
+                    return;
+                }
+                let kept = tasks.remove(kept.unwrap());
+                let start = tasks
+                    .iter()
+                    .chain([&kept])
+                    .map(|task| task.interval().unwrap().0);
+                let end = tasks
+                    .iter()
+                    .chain([&kept])
+                    .map(|task| task.interval().unwrap().1);
+                let (start, end) = (start.min().unwrap(), end.max().unwrap());
+                let end_format = if to_local(end).date() == to_local(start).date() {
+                    "%H:%M"
+                } else {
+                    "%Y-%m-%d %H:%M"
+                };
+
+                let mut changes = ChangeSet::new(api, &workspace);
+                let request = kept.request(start, Some(end));
+                let mut merged = changes.update(&kept, &request).await.is_some();
+                for task in &tasks {
+                    merged = merged && changes.delete(task).await.is_some();
+                }
+                if !merged {
+                    println!("Failed to merge tasks");
+                    if !changes.rollback().await {
+                        println!(
+                            "Some of the changes could not be undone, please check your tasks"
+                        );
+                    }
+                    return;
+                }
+                println!(
+                    "Merged {} tasks into '{}' ({} - {} [{}])",
+                    tasks.len() + 1,
+                    kept.description,
+                    to_local(start).format("%Y-%m-%d %H:%M"),
+                    to_local(end).format(end_format),
+                    format_duration(end - start)
+                );
+            }
+            AddSubCommand::BulkEdit(bulk_edit) => bulk_edit.run(cli).await,
+            AddSubCommand::Duplicate { id, date } => {
+                let workspace = cli.current_workspace().await;
+                if workspace.is_none() {
+                    return;
+                }
+                let workspace = workspace.unwrap();
+                let task = cli.choose_task(&workspace, Some(id)).await;
+                if task.is_none() {
+                    return;
+                }
+                let task = task.unwrap();
+                let date = parse_date(date);
+                if date.is_none() {
+                    println!("Invalid date");
+                    return;
+                }
+                let interval = task.interval();
+                if interval.is_none() {
+                    println!("A running task cannot be duplicated");
+                    return;
+                }
+                let (start, end) = interval.unwrap();
+                let start_at = datetime(date.unwrap(), to_local(start).time());
+                let end_at = start_at + (end - start);
+
+                let mut request = task.request(start_at, Some(end_at));
+                if !cli.resolve_overlaps(&workspace, &mut request).await {
+                    println!("Task not created");
+                    return;
+                }
+                let (start_at, end_at) = request.times().unwrap();
+                let end_at = end_at.unwrap();
+                let created = api.new_task(&workspace, &request).await;
+                if created.is_none() || !created.unwrap() {
+                    println!("Failed to duplicate task");
+                    return;
+                }
+                println!(
+                    "Task '{}' copied to {} - {}",
+                    task.description,
+                    to_local(start_at).format("%Y-%m-%d %H:%M"),
+                    to_local(end_at).format("%Y-%m-%d %H:%M")
+                );
+            }
+            AddSubCommand::Saved {
+                name,
+                date: task_date,
+                description,
+                workspace,
+                vars,
+            } => {
+                let entry = if let Some(name) = name {
+                    let entry = cli.api.manager.get_saved_task(name);
+                    if entry.is_none() {
+                        println!("Template '{}' not found", name);
+                        return;
+                    }
+                    entry.unwrap()
                 } else {
-                    saved.start = date(entry.start).to_rfc3339_opts(SecondsFormat::Millis, true);
-                    saved.end = date(entry.end).to_rfc3339_opts(SecondsFormat::Millis, true);
+                    let saved = cli.api.manager.get_saved_tasks();
+                    if saved.is_empty() {
+                        println!("No saved templates found");
+                        return;
+                    }
+                    require_input("the template", "pass its name");
+                    let names = saved
+                        .iter()
+                        .map(|saved| format!("{} [{}]", saved.name, saved.describe_time()));
+                    let idx = cli.pick("Select a saved template to create:", names.collect());
+                    if idx.is_none() {
+                        return;
+                    }
+                    saved[idx.unwrap()].clone()
+                };
+                let mut saved = entry.task.clone();
+                let workspace = match workspace.as_ref().or(entry.workspace_id.as_ref()) {
+                    Some(workspace) => api.find_workspace(workspace).await,
+                    None => cli.current_workspace().await,
+                };
+                if workspace.is_none() {
+                    println!("Workspace not found");
+                    return;
+                }
+                let workspace = workspace.unwrap();
+                let prompt = name.is_none();
+
+                if let Some(description) = description {
+                    saved.description = description.clone();
+                } else if prompt {
+                    let name = cli
+                        .select_text_opt(
+                            "Do you want to change the description?",
+                            Some(&saved.description),
+                        )
+                        .await;
+                    saved.description = name.unwrap_or(saved.description);
+                }
+
+                let task_date = match task_date {
+                    Some(task_date) => parse_date(task_date),
+                    None if prompt => {
+                        let date = cli.select_date(now().date());
+                        if date.is_none() {
+                            println!("Task not created");
+                            return;
+                        }
+                        date
+                    }
+                    None => Some(now().date()),
+                };
+                if task_date.is_none() {
+                    println!("Invalid date");
+                    return;
+                }
+                let task_date = task_date.unwrap();
+                // The last task ending on the date of the task, not the latest one overall
+                let last_end = match &entry.relative {
+                    Some(relative) if relative.anchor == TemplateAnchor::AfterLast => {
+                        let from = datetime(task_date, NaiveTime::MIN);
+                        let to = datetime(task_date + Duration::days(1), NaiveTime::MIN);
+                        let tasks = api.get_tasks_between(&workspace, from, to).await;
+                        let ends = tasks.unwrap_or_default().into_iter();
+                        let ends = ends.filter_map(|task| task.interval().map(|(_, end)| end));
+                        ends.filter(|end| to_local(*end).date() == task_date).max()
+                    }
+                    _ => None,
+                };
+                let mut values = HashMap::new();
+                for var in vars {
+                    let pair = var.split_once('=');
+                    if pair.is_none() {
+                        println!("Invalid variable '{}', expected KEY=VALUE", var);
+                        return;
+                    }
+                    let (key, value) = pair.unwrap();
+                    values.insert(key.trim().to_string(), value.to_string());
+                }
+                let mut entry = entry;
+                entry.task = saved;
+                let mut saved = fill_template(cli, &workspace, &entry, task_date, values).await;
+
+                let (start, end) = entry.resolve(task_date, last_end);
+                saved.start = start.to_rfc3339_opts(SecondsFormat::Millis, true);
+                saved.end = Some(end.to_rfc3339_opts(SecondsFormat::Millis, true));
+                if !cli.resolve_overlaps(&workspace, &mut saved).await {
+                    println!("Task not created");
+                    return;
                 }
                 let task = api.new_task(&workspace, &saved).await;
                 clear_screen();
@@ -155,3 +1031,102 @@ impl TaskCommand {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::Clockify, cfg::ConfigManager, prompt::ScriptedPrompter, utils::set_interactive,
+    };
+    use std::sync::Once;
+
+    fn cli(answers: &[&str]) -> ClockifyCLI {
+        static INTERACTIVE: Once = Once::new();
+        INTERACTIVE.call_once(|| set_interactive(true));
+        let answers = answers.iter().map(|answer| answer.to_string()).collect();
+        ClockifyCLI {
+            api: Clockify {
+                manager: ConfigManager {
+                    config: None,
+                    directory: None,
+                },
+            },
+            prompter: Box::new(ScriptedPrompter::new(answers)),
+            allow_overlap: false,
+        }
+    }
+
+    fn workspace() -> Workspace {
+        Workspace {
+            id: String::from("w1"),
+            name: String::from("Work"),
+        }
+    }
+
+    /// A draft with the project and tags already chosen, so nothing is fetched
+    fn draft() -> TaskDraft {
+        let project = Project {
+            id: String::from("p1"),
+            name: String::from("Website"),
+            duration: String::new(),
+        };
+        TaskDraft {
+            project: Some(project),
+            tags: Some(Vec::new()),
+            ..Default::default()
+        }
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    async fn ask(answers: &[&str], draft: &mut TaskDraft) -> bool {
+        let directory = DirectoryConfig::default();
+        ask_draft(&cli(answers), &workspace(), &directory, draft, false).await
+    }
+
+    #[tokio::test]
+    async fn moves_a_duration_end_with_the_start() {
+        let answers = [
+            "Standup",
+            "2023-04-03",
+            "9:00",
+            "1h30m",
+            "Edit the start time",
+            "10",
+            "Submit",
+        ];
+        let mut draft = draft();
+        assert!(ask(&answers, &mut draft).await);
+        assert_eq!(draft.description.as_deref(), Some("Standup"));
+        assert_eq!(draft.date, NaiveDate::from_ymd_opt(2023, 4, 3));
+        assert_eq!(draft.start, Some(time(10, 0)));
+        assert_eq!(draft.end, Some(time(11, 30)));
+    }
+
+    #[tokio::test]
+    async fn goes_back_a_step_when_cancelled() {
+        let answers = [
+            "Standup",
+            "<esc>",
+            "Planning",
+            "2023-04-03",
+            "9",
+            "17:00",
+            "Edit the start time",
+            "10",
+            "Submit",
+        ];
+        let mut draft = draft();
+        assert!(ask(&answers, &mut draft).await);
+        assert_eq!(draft.description.as_deref(), Some("Planning"));
+        assert_eq!(draft.start, Some(time(10, 0)));
+        assert_eq!(draft.end, Some(time(17, 0)));
+    }
+
+    #[tokio::test]
+    async fn cancels_on_the_first_step() {
+        assert!(!ask(&["<esc>"], &mut draft()).await);
+    }
+}
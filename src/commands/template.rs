@@ -0,0 +1,407 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::Duration;
+use clap::{Parser, Subcommand};
+
+use crate::{
+    api::ClockifyCLI,
+    cfg::{RelativeTime, SavedTask, TemplateAnchor, TemplateFile},
+    utils::{clear_screen, parse_span, parse_time, placeholders, require_input},
+};
+
+/// Manage the saved task templates
+#[derive(Debug, Parser)]
+pub struct TemplateCommand {
+    #[clap(subcommand)]
+    command: TemplateSubCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TemplateSubCommand {
+    /// List all saved templates
+    List,
+    /// Show the details of a saved template
+    Show {
+        /// The name of the template
+        name: String,
+    },
+    /// Edit a saved template, prompts for the changes when no flags are given
+    Edit {
+        /// The name of the template
+        name: String,
+        /// The new description of the task
+        #[clap(long)]
+        description: Option<String>,
+        /// The new start time (e.g. 9:30, 930, 9:30am)
+        #[clap(long)]
+        start: Option<String>,
+        /// The new end time, or the duration of the task (e.g. 17:30, 1h30m)
+        #[clap(long)]
+        end: Option<String>,
+        /// Whether the task is billable
+        #[clap(long)]
+        billable: Option<bool>,
+        /// Makes the template relative, placing a task of this duration (e.g. 30m, 1h30m)
+        #[clap(long)]
+        duration: Option<String>,
+        /// Where a relative template places the task
+        #[clap(long, value_enum)]
+        anchor: Option<TemplateAnchor>,
+        /// Replaces the extra tags of the template, may contain `{placeholders}`
+        #[clap(long = "tag", value_name = "NAME")]
+        tags: Option<Vec<String>>,
+        /// Makes the template use its fixed start and end times again
+        #[clap(long, conflicts_with_all = ["duration", "anchor"])]
+        fixed: bool,
+    },
+    /// Rename a saved template
+    Rename {
+        /// The current name of the template
+        name: String,
+        /// The new name of the template
+        new_name: String,
+    },
+    /// Delete a saved template
+    Delete {
+        /// The name of the template
+        name: String,
+    },
+    /// Export all saved templates to a TOML or JSON file
+    Export {
+        /// The file to write, JSON is used when the extension is .json
+        file: PathBuf,
+    },
+    /// Import saved templates from a TOML or JSON file
+    Import {
+        /// The file to read, JSON is used when the extension is .json
+        file: PathBuf,
+    },
+}
+
+fn is_json(file: &Path) -> bool {
+    file.extension().is_some_and(|ext| ext == "json")
+}
+
+fn print_template(saved: &SavedTask) {
+    println!("Name: {}", saved.name);
+    println!("Description: {}", saved.task.description);
+    println!("Time: {}", saved.describe_time());
+    println!(
+        "Billable: {}",
+        if saved.task.billable { "Yes" } else { "No" }
+    );
+    if let Some(workspace_id) = &saved.workspace_id {
+        println!("Workspace: {}", workspace_id);
+    }
+    if let Some(project_id) = &saved.task.project_id {
+        println!("Project: {}", project_id);
+    }
+    if let Some(task_id) = &saved.task.task_id {
+        println!("Task: {}", task_id);
+    }
+    if !saved.task.tag_ids.is_empty() {
+        println!("Tags: {}", saved.task.tag_ids.join(", "));
+    }
+    if !saved.tags.is_empty() {
+        println!("Extra tags: {}", saved.tags.join(", "));
+    }
+    let mut names = placeholders(&saved.task.description);
+    names.extend(saved.tags.iter().flat_map(|tag| placeholders(tag)));
+    names.sort();
+    names.dedup();
+    if !names.is_empty() {
+        println!("Variables: {}", names.join(", "));
+    }
+}
+
+async fn select_relative(cli: &ClockifyCLI) -> Option<RelativeTime> {
+    let anchors = [
+        ("Ending now", TemplateAnchor::EndNow),
+        ("Starting now", TemplateAnchor::StartNow),
+        (
+            "Starting when the last task ended",
+            TemplateAnchor::AfterLast,
+        ),
+        ("Starting at a fixed time", TemplateAnchor::StartAt),
+    ];
+    let labels = anchors.iter().map(|(label, _)| label.to_string()).collect();
+    let anchor = anchors[cli.pick("Where should the task be placed?", labels)?].1;
+    let duration = cli
+        .select_text("Enter the duration of the task (e.g. 30m, 1h30m)")
+        .await?;
+    let minutes = parse_span(&duration)?.num_minutes();
+    if minutes <= 0 {
+        return None;
+    }
+    Some(RelativeTime { anchor, minutes })
+}
+
+impl TemplateCommand {
+    pub async fn run(&self, cli: &mut ClockifyCLI) {
+        match &self.command {
+            TemplateSubCommand::List => {
+                let saved = cli.api.manager.get_saved_tasks();
+                if saved.is_empty() {
+                    println!("No saved templates found");
+                    return;
+                }
+                clear_screen();
+                println!("Saved templates:\n");
+                for (idx, saved) in saved.iter().enumerate() {
+                    println!(
+                        "[{}] {} - {} [{}]",
+                        idx + 1,
+                        saved.name,
+                        saved.task.description,
+                        saved.describe_time()
+                    );
+                }
+            }
+            TemplateSubCommand::Show { name } => {
+                let saved = cli.api.manager.get_saved_task(name);
+                if saved.is_none() {
+                    println!("Template '{}' not found", name);
+                    return;
+                }
+                clear_screen();
+                print_template(&saved.unwrap());
+            }
+            TemplateSubCommand::Edit {
+                name,
+                description,
+                start,
+                end,
+                billable,
+                duration,
+                anchor,
+                tags,
+                fixed,
+            } => {
+                let saved = cli.api.manager.get_saved_task(name);
+                if saved.is_none() {
+                    println!("Template '{}' not found", name);
+                    return;
+                }
+                let mut saved = saved.unwrap();
+                let interactive = description.is_none()
+                    && start.is_none()
+                    && end.is_none()
+                    && billable.is_none()
+                    && duration.is_none()
+                    && anchor.is_none()
+                    && tags.is_none()
+                    && !fixed;
+
+                if interactive {
+                    let hint = "pass them as flags, see --help";
+                    require_input("the changes", hint);
+                    let text = cli
+                        .select_text_opt(
+                            "Do you want to change the description?",
+                            Some(&saved.task.description),
+                        )
+                        .await;
+                    saved.task.description = text.unwrap_or(saved.task.description);
+
+                    if cli.select_bool("Do you want to change the time of the task?", hint) {
+                        if cli.select_bool(
+                            "Should the task be placed relative to when the template is applied?",
+                            hint,
+                        ) {
+                            let relative = select_relative(cli).await;
+                            if relative.is_none() {
+                                println!("Invalid duration");
+                                return;
+                            }
+                            let relative = relative.unwrap();
+                            if relative.anchor == TemplateAnchor::StartAt {
+                                let start = cli.select_time(None, Some(saved.start)).await;
+                                saved.start = start.unwrap_or(saved.start);
+                            }
+                            saved.relative = Some(relative);
+                        } else {
+                            let start = cli.select_time(None, Some(saved.start)).await;
+                            let end = cli.select_time(start, Some(saved.end)).await;
+                            saved.start = start.unwrap_or(saved.start);
+                            saved.end = end.unwrap_or(saved.end);
+                            saved.relative = None;
+                        }
+                    }
+
+                    if cli.select_bool("Do you want to change the project and tags?", hint) {
+                        let workspace = cli.select_workspace().await;
+                        if workspace.is_none() {
+                            return;
+                        }
+                        let workspace = workspace.unwrap();
+                        let project = cli.select_project(&workspace).await;
+                        let tags = cli.select_tags(&workspace, &saved.task.tag_ids).await;
+                        saved.workspace_id = Some(workspace.id.clone());
+                        if let Some(project) = project {
+                            saved.task.project_id = Some(project.id);
+                        }
+                        if let Some(tags) = tags {
+                            saved.task.tag_ids = tags.iter().map(|tag| tag.id.clone()).collect();
+                        }
+                    }
+
+                    saved.task.billable = cli.select_bool("Is this task billable?", hint);
+                } else {
+                    if let Some(description) = description {
+                        saved.task.description = description.clone();
+                    }
+                    if let Some(start) = start {
+                        let time = parse_time(start, None);
+                        if time.is_none() {
+                            println!("Invalid start time");
+                            return;
+                        }
+                        saved.start = time.unwrap();
+                    }
+                    if let Some(end) = end {
+                        let time = parse_time(end, Some(saved.start));
+                        if time.is_none() {
+                            println!("Invalid end time");
+                            return;
+                        }
+                        saved.end = time.unwrap();
+                    }
+                    saved.task.billable = billable.unwrap_or(saved.task.billable);
+                    if let Some(tags) = tags {
+                        saved.tags = tags.clone();
+                    }
+
+                    if *fixed {
+                        saved.relative = None;
+                    } else if duration.is_some() || anchor.is_some() {
+                        // Fixed times ending before they start span midnight
+                        let fixed_span = || {
+                            let span = saved.end - saved.start;
+                            if saved.end < saved.start {
+                                span + Duration::days(1)
+                            } else {
+                                span
+                            }
+                        };
+                        let minutes = match duration {
+                            Some(duration) => parse_span(duration).map(|span| span.num_minutes()),
+                            None => Some(saved.relative.as_ref().map_or_else(
+                                || fixed_span().num_minutes(),
+                                |relative| relative.minutes,
+                            )),
+                        };
+                        if minutes.is_none() {
+                            println!("Invalid duration");
+                            return;
+                        }
+                        if minutes.unwrap() <= 0 {
+                            println!("The duration must be at least one minute");
+                            return;
+                        }
+                        saved.relative = Some(RelativeTime {
+                            anchor: anchor
+                                .or(saved.relative.as_ref().map(|relative| relative.anchor))
+                                .unwrap_or(TemplateAnchor::EndNow),
+                            minutes: minutes.unwrap(),
+                        });
+                    }
+                }
+
+                if saved.relative.is_none() && saved.start == saved.end {
+                    println!("The task must end after it starts");
+                    return;
+                }
+
+                let mgr = &mut cli.api.manager;
+                mgr.update_saved_task(name, saved.clone());
+                mgr.save();
+                clear_screen();
+                println!("Template updated successfully\n");
+                print_template(&saved);
+            }
+            TemplateSubCommand::Rename { name, new_name } => {
+                let mgr = &mut cli.api.manager;
+                if mgr.get_saved_task(new_name).is_some() {
+                    println!("A template named '{}' already exists", new_name);
+                    return;
+                }
+                let saved = mgr.get_saved_task(name);
+                if saved.is_none() {
+                    println!("Template '{}' not found", name);
+                    return;
+                }
+                let mut saved = saved.unwrap();
+                saved.name = new_name.clone();
+                mgr.update_saved_task(name, saved);
+                mgr.save();
+                println!("Template '{}' renamed to '{}'", name, new_name);
+            }
+            TemplateSubCommand::Delete { name } => {
+                let mgr = &mut cli.api.manager;
+                if mgr.remove_saved_task(name).is_none() {
+                    println!("Template '{}' not found", name);
+                    return;
+                }
+                mgr.save();
+                println!("Template '{}' deleted successfully", name);
+            }
+            TemplateSubCommand::Export { file } => {
+                let templates = TemplateFile {
+                    saved_tasks: cli.api.manager.get_saved_tasks(),
+                };
+                let contents = if is_json(file) {
+                    serde_json::to_string_pretty(&templates).map_err(|err| err.to_string())
+                } else {
+                    toml::to_string(&templates).map_err(|err| err.to_string())
+                };
+                if contents.is_err() {
+                    println!("Failed to serialize templates: {}", contents.err().unwrap());
+                    return;
+                }
+                if let Err(err) = fs::write(file, contents.unwrap()) {
+                    println!("Failed to write {}: {}", file.display(), err);
+                    return;
+                }
+                println!(
+                    "Exported {} templates to {}",
+                    templates.saved_tasks.len(),
+                    file.display()
+                );
+            }
+            TemplateSubCommand::Import { file } => {
+                let contents = fs::read_to_string(file);
+                if contents.is_err() {
+                    println!(
+                        "Failed to read {}: {}",
+                        file.display(),
+                        contents.err().unwrap()
+                    );
+                    return;
+                }
+                let contents = contents.unwrap();
+                let templates: Result<TemplateFile, String> = if is_json(file) {
+                    serde_json::from_str(&contents).map_err(|err| err.to_string())
+                } else {
+                    toml::from_str(&contents).map_err(|err| err.to_string())
+                };
+                if templates.is_err() {
+                    println!(
+                        "Failed to parse {}: {}",
+                        file.display(),
+                        templates.err().unwrap()
+                    );
+                    return;
+                }
+                let templates = templates.unwrap().saved_tasks;
+                let total = templates.len();
+                let mgr = &mut cli.api.manager;
+                let replaced = mgr.import_saved_tasks(templates);
+                mgr.save();
+                println!("Imported {} templates ({} replaced)", total, replaced);
+            }
+        }
+    }
+}
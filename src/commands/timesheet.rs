@@ -0,0 +1,222 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Duration, NaiveTime, Weekday};
+use clap::{Parser, ValueEnum};
+
+use crate::{
+    api::{ClockifyCLI, Task},
+    utils::{
+        datetime, format_duration, now, parse_week, parse_weekday, round_duration, split_days,
+        week_start,
+    },
+};
+
+/// Show a week as a grid of projects by weekday, with the totals of each row and day
+#[derive(Debug, Parser)]
+pub struct TimesheetCommand {
+    /// The week to show, as an ISO week (e.g. 2023-W14) or any day in it (e.g. -1w)
+    #[clap(long)]
+    week: Option<String>,
+    /// The first day of the week, defaults to the configured one
+    #[clap(long, value_name = "DAY")]
+    week_start: Option<String>,
+    /// Rounds every duration to this many minutes, defaults to the configured rounding
+    #[clap(long, value_name = "MINUTES")]
+    round: Option<u32>,
+    /// What each row shows
+    #[clap(long, value_enum, default_value_t = Breakdown::Project)]
+    by: Breakdown,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum Breakdown {
+    Project,
+    /// The project and the task of the project
+    Task,
+    Tag,
+}
+
+impl TimesheetCommand {
+    pub async fn run(&self, cli: &ClockifyCLI) {
+        let api = &cli.api;
+        let config = api.manager.config.as_ref().unwrap();
+        let first = match &self.week_start {
+            Some(day) => parse_weekday(day),
+            None => Some(config.week_start.unwrap_or(Weekday::Mon)),
+        };
+        if first.is_none() {
+            println!("Unknown day '{}'", self.week_start.as_ref().unwrap());
+            return;
+        }
+        let first = first.unwrap();
+        let start = match &self.week {
+            Some(week) => parse_week(week, first),
+            None => Some(week_start(now().date(), first)),
+        };
+        if start.is_none() {
+            println!("Invalid week, expected e.g. 2023-W14");
+            return;
+        }
+        let start = start.unwrap();
+        let rounding = self.round.or(config.rounding).unwrap_or(0);
+
+        let workspace = cli.current_workspace().await;
+        if workspace.is_none() {
+            return;
+        }
+        let workspace = workspace.unwrap();
+        // The day before is fetched too, for the tasks running past midnight into the week
+        let from = datetime(start - Duration::days(1), NaiveTime::MIN);
+        let to = datetime(start + Duration::days(7), NaiveTime::MIN);
+        let tasks = api.get_tasks_between(&workspace, from, to).await;
+        if tasks.is_none() {
+            return;
+        }
+        let tasks = tasks.unwrap();
+
+        let projects = api.get_projects(&workspace).await.unwrap_or_default();
+        let projects = projects
+            .into_iter()
+            .map(|project| (project.id.clone(), project))
+            .collect::<HashMap<_, _>>();
+        let tags = api.get_tags(&workspace).await.unwrap_or_default();
+        let tags = tags
+            .into_iter()
+            .map(|tag| (tag.id, tag.name))
+            .collect::<HashMap<_, _>>();
+        let mut task_names = HashMap::new();
+        if self.by == Breakdown::Task {
+            for task in &tasks {
+                let project = task.project_id.as_ref().and_then(|id| projects.get(id));
+                if project.is_none() || task.task_id.is_none() {
+                    continue;
+                }
+                let project = project.unwrap();
+                if task_names.contains_key(&project.id) {
+                    continue;
+                }
+                let entries = api.get_project_tasks(&workspace, project).await;
+                let entries = entries.unwrap_or_default().into_iter();
+                let names = entries.map(|entry| (entry.id, entry.name));
+                task_names.insert(project.id.clone(), names.collect::<HashMap<_, _>>());
+            }
+        }
+        let project_name = |task: &Task| {
+            let project = task.project_id.as_ref().and_then(|id| projects.get(id));
+            project.map_or(String::from("(no project)"), |project| project.name.clone())
+        };
+        let rows_of = |task: &Task| -> Vec<String> {
+            match self.by {
+                Breakdown::Project => vec![project_name(task)],
+                Breakdown::Task => {
+                    let name = task.project_id.as_ref().and_then(|project| {
+                        let names = task_names.get(project)?;
+                        names.get(task.task_id.as_ref()?)
+                    });
+                    match name {
+                        Some(name) => vec![format!("{} / {}", project_name(task), name)],
+                        None => vec![project_name(task)],
+                    }
+                }
+                Breakdown::Tag => {
+                    let ids = task.tag_ids.clone().unwrap_or_default();
+                    if ids.is_empty() {
+                        return vec![String::from("(no tag)")];
+                    }
+                    let names = ids.iter().map(|id| tags.get(id).unwrap_or(id).clone());
+                    names.collect()
+                }
+            }
+        };
+
+        let end = start + Duration::days(6);
+        let mut rows: BTreeMap<String, [Duration; 7]> = BTreeMap::new();
+        let mut days = [Duration::zero(); 7];
+        for task in &tasks {
+            let started = task.start();
+            if started.is_none() {
+                continue;
+            }
+            let started = started.unwrap();
+            // Tasks running past midnight count on each day they ran
+            for (date, duration) in split_days((started, started + task.duration())) {
+                let day = (date - start).num_days();
+                if !(0..7).contains(&day) {
+                    continue;
+                }
+                let day = day as usize;
+                days[day] += duration;
+                for row in rows_of(task) {
+                    let cells = rows.entry(row).or_insert([Duration::zero(); 7]);
+                    cells[day] += duration;
+                }
+            }
+        }
+        // The totals add up the rounded cells, so they match what is shown
+        for cells in rows.values_mut() {
+            for cell in cells.iter_mut() {
+                *cell = round_duration(*cell, rounding);
+            }
+        }
+        for (day, total) in days.iter_mut().enumerate() {
+            *total = match self.by {
+                Breakdown::Tag => round_duration(*total, rounding),
+                _ => rows.values().map(|cells| cells[day]).sum(),
+            };
+        }
+
+        println!(
+            "Week {} ({} - {})\n",
+            (start + Duration::days(3)).format("%G-W%V"),
+            start.format("%Y-%m-%d"),
+            end.format("%Y-%m-%d")
+        );
+        if rows.is_empty() {
+            println!("No tasks found");
+            return;
+        }
+        let cell = |duration: Duration| {
+            if duration.is_zero() {
+                return String::from("-");
+            }
+            format_duration(duration)
+        };
+        let mut header = vec![String::new()];
+        for day in 0..7 {
+            let date = start + Duration::days(day);
+            header.push(date.format("%a %d").to_string());
+        }
+        header.push(String::from("Total"));
+        let mut table = vec![header];
+        for (name, cells) in &rows {
+            let mut line = vec![name.clone()];
+            line.extend(cells.iter().map(|duration| cell(*duration)));
+            line.push(cell(cells.iter().sum()));
+            table.push(line);
+        }
+        let mut totals = vec![String::from("Total")];
+        totals.extend(days.iter().map(|duration| cell(*duration)));
+        totals.push(cell(days.iter().sum()));
+        table.push(totals);
+
+        let widths = (0..table[0].len())
+            .map(|column| {
+                table
+                    .iter()
+                    .map(|line| line[column].chars().count())
+                    .max()
+                    .unwrap()
+            })
+            .collect::<Vec<usize>>();
+        for line in &table {
+            let mut text = format!("{:<width$}", line[0], width = widths[0]);
+            for (column, value) in line.iter().enumerate().skip(1) {
+                text.push_str(&format!("  {:>width$}", value, width = widths[column]));
+            }
+            println!("{}", text.trim_end());
+        }
+        if self.by == Breakdown::Tag {
+            println!("\nTasks with several tags are counted in each of their rows");
+        }
+    }
+}
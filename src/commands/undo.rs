@@ -0,0 +1,63 @@
+use clap::Parser;
+
+use crate::{
+    api::ClockifyCLI,
+    journal::{self, Operation},
+};
+
+/// Revert the latest changes made to the tasks, see `history`
+#[derive(Debug, Parser)]
+pub struct UndoCommand {
+    /// How many changes to revert
+    #[clap(default_value_t = 1)]
+    count: usize,
+}
+
+impl UndoCommand {
+    pub async fn run(&self, cli: &ClockifyCLI) {
+        let api = &cli.api;
+        let mut entries = journal::load();
+        if entries.is_empty() {
+            println!("Nothing to undo");
+            return;
+        }
+        let mut undone = 0;
+        while undone < self.count {
+            let entry = entries.pop();
+            if entry.is_none() {
+                break;
+            }
+            let entry = entry.unwrap();
+            let workspace = &entry.workspace;
+            let reverted = match &entry.operation {
+                Operation::Create { task } => {
+                    api.remove_task(workspace, task).await.unwrap_or(false)
+                }
+                Operation::Update { before, after } => {
+                    let request = before.to_request();
+                    api.put_task(workspace, after, &request).await.is_some()
+                }
+                Operation::Delete { task } => {
+                    let created = api.post_task(workspace, &task.to_request()).await;
+                    if let Some(created) = &created {
+                        for older in entries.iter_mut() {
+                            older.operation.rename(&task.id, &created.id);
+                        }
+                    }
+                    created.is_some()
+                }
+            };
+            if !reverted {
+                println!("Failed to revert: {}", entry.operation.describe());
+                entries.push(entry);
+                break;
+            }
+            println!("Reverted: {}", entry.operation.describe());
+            undone += 1;
+        }
+        journal::save(&entries);
+        if undone < self.count {
+            println!("{} of {} changes reverted", undone, self.count);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::fs;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{Task, Workspace};
+
+/// The file the journal is kept in, next to `Config.toml`
+pub const FILE_NAME: &str = "History.jsonl";
+
+/// How many operations the journal keeps, the oldest ones are dropped first
+pub const MAX_ENTRIES: usize = 500;
+
+/// A change made to the tasks, with the full tasks needed to reverse it
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Operation {
+    Create { task: Task },
+    Update { before: Task, after: Task },
+    Delete { task: Task },
+}
+
+impl Operation {
+    /// The tasks the operation refers to
+    fn tasks_mut(&mut self) -> Vec<&mut Task> {
+        match self {
+            Operation::Create { task } | Operation::Delete { task } => vec![task],
+            Operation::Update { before, after } => vec![before, after],
+        }
+    }
+
+    /// Describes the operation, e.g. `Deleted 'Standup' (2023-04-03 09:00 - 09:15)`
+    pub fn describe(&self) -> String {
+        match self {
+            Operation::Create { task } => {
+                format!("Created '{}' ({})", task.description, task.time.describe())
+            }
+            Operation::Update { before, after } if before.description != after.description => {
+                format!(
+                    "Changed '{}' to '{}' ({})",
+                    before.description,
+                    after.description,
+                    after.time.describe()
+                )
+            }
+            Operation::Update { before, after } => format!(
+                "Changed '{}' ({} to {})",
+                before.description,
+                before.time.describe(),
+                after.time.describe()
+            ),
+            Operation::Delete { task } => {
+                format!("Deleted '{}' ({})", task.description, task.time.describe())
+            }
+        }
+    }
+
+    /// Points the operation to the task recreated with `new_id`
+    pub fn rename(&mut self, old_id: &str, new_id: &str) {
+        for task in self.tasks_mut() {
+            if task.id == old_id {
+                task.id = new_id.to_string();
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+    pub time: DateTime<Utc>,
+    pub workspace: Workspace,
+    pub operation: Operation,
+}
+
+/// Appends an operation to the journal, dropping the oldest ones past [`MAX_ENTRIES`]
+pub fn record(workspace: &Workspace, operation: Operation) {
+    let mut entries = load();
+    entries.push(JournalEntry {
+        time: Utc::now(),
+        workspace: workspace.clone(),
+        operation,
+    });
+    if entries.len() > MAX_ENTRIES {
+        entries.drain(..entries.len() - MAX_ENTRIES);
+    }
+    save(&entries);
+}
+
+/// Reads the journal, oldest operation first
+pub fn load() -> Vec<JournalEntry> {
+    let contents = fs::read_to_string(FILE_NAME).unwrap_or_default();
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Replaces the journal with the given operations
+pub fn save(entries: &[JournalEntry]) {
+    let mut contents = String::new();
+    for entry in entries {
+        contents.push_str(&serde_json::to_string(entry).unwrap());
+        contents.push('\n');
+    }
+    if let Err(err) = fs::write(FILE_NAME, contents) {
+        println!("Failed to write {}: {}", FILE_NAME, err);
+    }
+}
@@ -1,19 +1,53 @@
-#![feature(fs_try_exists)]
 use api::{Clockify, ClockifyCLI};
 use cfg::ConfigManager;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use commands::{
-    config::ConfigCommand, projects::ProjectsCommand, tags::TagsCommand, task::TaskCommand,
+    audit::AuditCommand, calendar::CalendarCommand, config::ConfigCommand, gaps::GapsCommand,
+    history::HistoryCommand, log::LogCommand, projects::ProjectsCommand, tags::TagsCommand,
+    task::TaskCommand, template::TemplateCommand, timesheet::TimesheetCommand, undo::UndoCommand,
+};
+use prompt::{Prompter, ScriptedPrompter, TerminalPrompter};
+use std::path::PathBuf;
+use utils::{
+    set_clear, set_color, set_duration_format, set_interactive, set_zone, terminal, DurationFormat,
+    Zone,
 };
 
 pub mod api;
 pub mod cfg;
+pub mod changes;
 pub mod commands;
+pub mod journal;
+pub mod prompt;
 pub mod utils;
 
 #[derive(Debug, Parser)]
 #[clap(name = "clockify", version)]
-pub enum App {
+pub struct App {
+    /// The IANA timezone used for the times of the tasks (e.g. Europe/Madrid)
+    #[clap(long, global = true)]
+    tz: Option<String>,
+    /// How durations are shown
+    #[clap(long, global = true, value_enum)]
+    duration_format: Option<DurationFormat>,
+    /// Never clears the screen
+    #[clap(long, global = true)]
+    no_clear: bool,
+    /// Never clears the screen and asks line by line, without colors or moving the cursor
+    #[clap(long, global = true)]
+    plain: bool,
+    /// Answers the prompts from a file, one answer per line
+    #[clap(long, global = true, value_name = "FILE")]
+    answers: Option<PathBuf>,
+    /// Creates tasks that overlap the ones already logged without asking what to do
+    #[clap(long, global = true)]
+    allow_overlap: bool,
+    #[clap(subcommand)]
+    command: AppCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AppCommand {
     Config(ConfigCommand),
     /// Manage clockify tasks
     Task(TaskCommand),
@@ -21,19 +55,71 @@ pub enum App {
     Tags(TagsCommand),
     /// List all clockify projects which are available to the user
     Projects(ProjectsCommand),
+    Template(TemplateCommand),
+    Log(LogCommand),
+    Undo(UndoCommand),
+    History(HistoryCommand),
+    Timesheet(TimesheetCommand),
+    Calendar(CalendarCommand),
+    Gaps(GapsCommand),
+    Audit(AuditCommand),
 }
 
 #[tokio::main]
 async fn main() {
-    let mut mgr = ConfigManager { config: None };
+    let mut mgr = ConfigManager {
+        config: None,
+        directory: None,
+    };
     mgr.load();
-    let api = Clockify { manager: mgr };
-    let mut cli = ClockifyCLI { api };
     let args = App::parse();
-    match args {
-        App::Config(config) => config.run(&mut cli).await,
-        App::Task(task) => task.run(&mut cli).await,
-        App::Tags(tags) => tags.run(&cli).await,
-        App::Projects(projects) => projects.run(&cli).await,
+    let timezone = args
+        .tz
+        .clone()
+        .or(mgr.config.as_ref().unwrap().timezone.clone());
+    if let Some(timezone) = timezone {
+        let zone = Zone::parse(&timezone);
+        if zone.is_none() {
+            println!("Unknown timezone '{}'", timezone);
+            return;
+        }
+        set_zone(zone.unwrap());
+    }
+    set_clear(!args.no_clear && !args.plain);
+    set_color(!args.plain);
+    let config = mgr.config.as_ref().unwrap();
+    set_duration_format(args.duration_format.unwrap_or(config.duration_format));
+    let prompter: Box<dyn Prompter> = match &args.answers {
+        Some(file) => match ScriptedPrompter::load(file) {
+            Ok(prompter) => Box::new(prompter),
+            Err(err) => {
+                println!("Failed to read {}: {}", file.display(), err);
+                return;
+            }
+        },
+        // The terminal prompts move the cursor around, so plain prompts are read line by line
+        None if args.plain => Box::new(ScriptedPrompter::stdin()),
+        None => Box::new(TerminalPrompter),
+    };
+    set_interactive(args.answers.is_some() || terminal());
+    let api = Clockify { manager: mgr };
+    let mut cli = ClockifyCLI {
+        api,
+        prompter,
+        allow_overlap: args.allow_overlap,
+    };
+    match args.command {
+        AppCommand::Config(config) => config.run(&mut cli).await,
+        AppCommand::Task(task) => task.run(&mut cli).await,
+        AppCommand::Tags(tags) => tags.run(&cli).await,
+        AppCommand::Projects(projects) => projects.run(&cli).await,
+        AppCommand::Template(template) => template.run(&mut cli).await,
+        AppCommand::Log(log) => log.run(&cli).await,
+        AppCommand::Undo(undo) => undo.run(&cli).await,
+        AppCommand::History(history) => history.run().await,
+        AppCommand::Timesheet(timesheet) => timesheet.run(&cli).await,
+        AppCommand::Calendar(calendar) => calendar.run(&cli).await,
+        AppCommand::Gaps(gaps) => gaps.run(&cli).await,
+        AppCommand::Audit(audit) => audit.run(&cli).await,
     }
 }
@@ -0,0 +1,372 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fs,
+    io::{self, Write},
+    path::Path,
+    process,
+    rc::Rc,
+};
+
+use chrono::NaiveDate;
+use inquire::{validator::Validation, Confirm, DateSelect, MultiSelect, Select, Text};
+
+use crate::utils::{fuzzy_filter, parse_date};
+
+/// Checks a typed answer, returning the message to show when it is invalid
+pub type Validator = Rc<dyn Fn(&str) -> Result<(), String>>;
+
+/// Asks the user for input. Every method returns `None` when the prompt is cancelled.
+pub trait Prompter {
+    /// Asks for a line of text, pre-filled with `default`
+    fn text(
+        &self,
+        message: &str,
+        default: Option<&str>,
+        help: Option<&str>,
+        validator: Option<Validator>,
+    ) -> Option<String>;
+    /// Asks for one of the options and returns its index
+    fn select(&self, message: &str, options: Vec<String>) -> Option<usize>;
+    /// Asks for any number of the options, starting with the `selected` ones
+    fn multi_select(
+        &self,
+        message: &str,
+        options: Vec<String>,
+        selected: &[usize],
+    ) -> Option<Vec<usize>>;
+    /// Asks a yes or no question
+    fn confirm(&self, message: &str) -> Option<bool>;
+    /// Asks for a date from a calendar
+    fn calendar(&self, message: &str, default: NaiveDate) -> Option<NaiveDate>;
+}
+
+/// Prompts in the terminal
+pub struct TerminalPrompter;
+
+impl Prompter for TerminalPrompter {
+    fn text(
+        &self,
+        message: &str,
+        default: Option<&str>,
+        help: Option<&str>,
+        validator: Option<Validator>,
+    ) -> Option<String> {
+        let mut prompt = Text::new(message).with_initial_value(default.unwrap_or(""));
+        if let Some(help) = help {
+            prompt = prompt.with_help_message(help);
+        }
+        if let Some(validator) = validator {
+            prompt = prompt.with_validator(move |input: &str| match validator(input) {
+                Ok(()) => Ok(Validation::Valid),
+                Err(message) => Ok(Validation::Invalid(message.into())),
+            });
+        }
+        prompt.prompt().ok()
+    }
+
+    fn select(&self, message: &str, options: Vec<String>) -> Option<usize> {
+        Select::new(message, options)
+            .with_filter(&fuzzy_filter)
+            .with_page_size(15)
+            .raw_prompt()
+            .ok()
+            .map(|option| option.index)
+    }
+
+    fn multi_select(
+        &self,
+        message: &str,
+        options: Vec<String>,
+        selected: &[usize],
+    ) -> Option<Vec<usize>> {
+        MultiSelect::new(message, options)
+            .with_filter(&fuzzy_filter)
+            .with_default(selected)
+            .with_page_size(15)
+            .raw_prompt()
+            .ok()
+            .map(|options| options.into_iter().map(|option| option.index).collect())
+    }
+
+    fn confirm(&self, message: &str) -> Option<bool> {
+        Confirm::new(message).with_default(false).prompt().ok()
+    }
+
+    fn calendar(&self, message: &str, default: NaiveDate) -> Option<NaiveDate> {
+        DateSelect::new(message).with_default(default).prompt().ok()
+    }
+}
+
+/// Answers the prompts line by line, either from a file or typed in a plain terminal.
+///
+/// An empty line keeps the default, `<esc>` cancels the prompt, options are chosen by
+/// their name, a unique prefix or their number and several options are separated by commas.
+pub struct ScriptedPrompter {
+    /// The answers left, `None` when they are typed in the terminal
+    answers: Option<RefCell<VecDeque<String>>>,
+}
+
+/// The answer that cancels a prompt, like pressing Esc
+const ESCAPE: &str = "<esc>";
+
+impl ScriptedPrompter {
+    pub fn new(answers: Vec<String>) -> ScriptedPrompter {
+        ScriptedPrompter {
+            answers: Some(RefCell::new(answers.into())),
+        }
+    }
+
+    pub fn load(file: &Path) -> Result<ScriptedPrompter, String> {
+        let contents = fs::read_to_string(file).map_err(|err| err.to_string())?;
+        Ok(ScriptedPrompter::new(
+            contents.lines().map(str::to_string).collect(),
+        ))
+    }
+
+    /// Reads the answers typed in the terminal, without moving the cursor or using colors
+    pub fn stdin() -> ScriptedPrompter {
+        ScriptedPrompter { answers: None }
+    }
+
+    /// Takes the next answer, `None` when it cancels the prompt
+    fn next(&self, message: &str) -> Option<String> {
+        let answer = match &self.answers {
+            Some(answers) => {
+                let answer = answers.borrow_mut().pop_front();
+                if answer.is_none() {
+                    fail(message, "no answers left");
+                }
+                let answer = answer.unwrap();
+                println!("{} {}", message, answer);
+                answer
+            }
+            None => {
+                print!("{} ", message);
+                io::stdout().flush().ok()?;
+                let mut answer = String::new();
+                // The end of the input cancels the prompt
+                if io::stdin().read_line(&mut answer).ok()? == 0 {
+                    println!();
+                    return None;
+                }
+                answer.trim_end_matches(['\r', '\n']).to_string()
+            }
+        };
+        if answer.trim() == ESCAPE {
+            return None;
+        }
+        Some(answer)
+    }
+
+    /// Rejects an answer: a script stops, as the following answers would be out of step, and
+    /// an answer typed in the terminal is asked for again
+    fn reject(&self, message: &str, reason: &str) {
+        if self.answers.is_some() {
+            fail(message, reason);
+        }
+        println!("{}", reason);
+    }
+
+    /// Lists the options when they are chosen in the terminal
+    fn show(&self, options: &[String], selected: &[usize]) {
+        if self.answers.is_some() {
+            return;
+        }
+        for (idx, option) in options.iter().enumerate() {
+            let mark = if selected.contains(&idx) { "*" } else { " " };
+            println!("{} {:>2}) {}", mark, idx + 1, option);
+        }
+    }
+}
+
+/// Stops when an answer cannot be used, as the following answers would be out of step
+fn fail(message: &str, reason: &str) -> ! {
+    eprintln!("Cannot answer '{}': {}", message, reason);
+    process::exit(2);
+}
+
+/// Finds the option named by the answer: its name, its number, a unique prefix or a
+/// unique fuzzy match
+fn find_option(options: &[String], answer: &str) -> Option<usize> {
+    let answer = answer.trim();
+    if let Some(idx) = options
+        .iter()
+        .position(|option| option.eq_ignore_ascii_case(answer))
+    {
+        return Some(idx);
+    }
+    match answer.parse::<usize>() {
+        Ok(idx) if idx >= 1 && idx <= options.len() => return Some(idx - 1),
+        _ => {}
+    }
+    let lower = answer.to_lowercase();
+    let matches: [&dyn Fn(&String) -> bool; 2] = [
+        &|option| option.to_lowercase().starts_with(&lower),
+        &|option| fuzzy_filter(answer, option, option, 0),
+    ];
+    for matches in matches {
+        let mut found = options
+            .iter()
+            .enumerate()
+            .filter(|(_, option)| matches(option));
+        if let (Some((idx, _)), None) = (found.next(), found.next()) {
+            return Some(idx);
+        }
+    }
+    None
+}
+
+impl Prompter for ScriptedPrompter {
+    fn text(
+        &self,
+        message: &str,
+        default: Option<&str>,
+        help: Option<&str>,
+        validator: Option<Validator>,
+    ) -> Option<String> {
+        let mut prompt = message.to_string();
+        if self.answers.is_none() {
+            if let Some(help) = help {
+                prompt.push_str(&format!(" ({})", help));
+            }
+            if let Some(default) = default.filter(|default| !default.is_empty()) {
+                prompt.push_str(&format!(" [{}]", default));
+            }
+        }
+        loop {
+            let answer = self.next(&prompt)?;
+            let answer = match default {
+                Some(default) if answer.is_empty() => default.to_string(),
+                _ => answer,
+            };
+            match validator.as_ref().map(|validator| validator(&answer)) {
+                Some(Err(reason)) => self.reject(message, &reason),
+                _ => return Some(answer),
+            }
+        }
+    }
+
+    fn select(&self, message: &str, options: Vec<String>) -> Option<usize> {
+        self.show(&options, &[]);
+        loop {
+            let answer = self.next(message)?;
+            match find_option(&options, &answer) {
+                Some(idx) => return Some(idx),
+                None => self.reject(message, &no_option(&answer)),
+            }
+        }
+    }
+
+    fn multi_select(
+        &self,
+        message: &str,
+        options: Vec<String>,
+        selected: &[usize],
+    ) -> Option<Vec<usize>> {
+        self.show(&options, selected);
+        'answer: loop {
+            let answer = self.next(message)?;
+            if answer.trim().is_empty() {
+                return Some(selected.to_vec());
+            }
+            let mut chosen = Vec::new();
+            for name in answer.split(',').filter(|name| !name.trim().is_empty()) {
+                let idx = find_option(&options, name);
+                if idx.is_none() {
+                    self.reject(message, &no_option(name));
+                    continue 'answer;
+                }
+                if !chosen.contains(&idx.unwrap()) {
+                    chosen.push(idx.unwrap());
+                }
+            }
+            return Some(chosen);
+        }
+    }
+
+    fn confirm(&self, message: &str) -> Option<bool> {
+        let prompt = match self.answers {
+            Some(_) => message.to_string(),
+            None => format!("{} (y/N)", message),
+        };
+        loop {
+            let answer = self.next(&prompt)?;
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" | "true" => return Some(true),
+                "" | "n" | "no" | "false" => return Some(false),
+                _ => self.reject(message, &format!("expected yes or no, got '{}'", answer)),
+            }
+        }
+    }
+
+    fn calendar(&self, message: &str, default: NaiveDate) -> Option<NaiveDate> {
+        loop {
+            let answer = self.next(message)?;
+            if answer.trim().is_empty() {
+                return Some(default);
+            }
+            match parse_date(&answer) {
+                Some(date) => return Some(date),
+                None => self.reject(message, &format!("invalid date '{}'", answer)),
+            }
+        }
+    }
+}
+
+fn no_option(answer: &str) -> String {
+    format!("no single option matches '{}'", answer.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> Vec<String> {
+        ["Website", "Web app", "Internal"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    #[test]
+    fn finds_options() {
+        let options = options();
+        assert_eq!(find_option(&options, " web app "), Some(1));
+        assert_eq!(find_option(&options, "3"), Some(2));
+        assert_eq!(find_option(&options, "webs"), Some(0));
+        assert_eq!(find_option(&options, "intl"), Some(2));
+        assert_eq!(find_option(&options, "web"), None);
+    }
+
+    #[test]
+    fn parses_answers() {
+        let answers = [
+            "",
+            "Review",
+            "<esc>",
+            "yes",
+            "",
+            "3,web app,3",
+            "",
+            "2023-04-03",
+        ];
+        let prompter = ScriptedPrompter::new(answers.map(String::from).to_vec());
+        let text =
+            |prompter: &ScriptedPrompter| prompter.text("Text:", Some("Standup"), None, None);
+        assert_eq!(text(&prompter).as_deref(), Some("Standup"));
+        assert_eq!(text(&prompter).as_deref(), Some("Review"));
+        assert_eq!(prompter.select("Project:", options()), None);
+        assert_eq!(prompter.confirm("Billable?"), Some(true));
+        assert_eq!(prompter.confirm("Billable?"), Some(false));
+        assert_eq!(
+            prompter.multi_select("Tags:", options(), &[]),
+            Some(vec![2, 1])
+        );
+        assert_eq!(
+            prompter.multi_select("Tags:", options(), &[0]),
+            Some(vec![0])
+        );
+        let date = NaiveDate::from_ymd_opt(2023, 4, 3);
+        assert_eq!(prompter.calendar("Date:", NaiveDate::MIN), date);
+    }
+}
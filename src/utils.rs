@@ -1,93 +1,547 @@
-use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc, NaiveDate};
+use chrono::{
+    DateTime, Datelike, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Offset,
+    TimeZone, Timelike, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use clap::ValueEnum;
+use iso8601_duration::Duration as IsoDuration;
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    io::{self, Write},
+    io::{self, IsTerminal, Write},
+    process::{self, Command},
+    sync::OnceLock,
 };
 
 lazy_static! {
-    static ref TIME_NAMES: HashMap<&'static str, &'static str> =
-        vec![("H", "hours"), ("M", "minutes"), ("S", "seconds")]
-            .into_iter()
-            .collect();
-}
-
-pub(crate) fn parse_duration(duration: &str) -> String {
-    let mut output = String::new();
-    let regex = Regex::new(r"[a-zA-Z]").unwrap();
-    let result = regex.split(duration);
-    let process = result.collect::<Vec<&str>>();
-    let matches = duration
-        .match_indices(&regex)
-        .collect::<Vec<(usize, &str)>>();
-    match process.len() - 1 {
-        1 => {
-            let time = process[0].parse::<u32>().unwrap();
-
-            if time == 0 {
-                return String::from("No time registered");
+    static ref TIME: Regex = Regex::new(r"^(\d{1,4})(?:[:.h](\d{2}))?\s*(am|pm|a|p)?$").unwrap();
+    static ref DATE_OFFSET: Regex = Regex::new(r"^([+-])(\d+)\s*([dw])$").unwrap();
+    static ref ISO_WEEK: Regex = Regex::new(r"(?i)^(\d{4})-?W(\d{1,2})$").unwrap();
+    static ref PLACEHOLDER: Regex = Regex::new(r"\{([A-Za-z_][A-Za-z0-9_-]*)\}").unwrap();
+}
+
+/// How durations are shown to the user
+#[derive(Serialize, Deserialize, ValueEnum, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum DurationFormat {
+    /// Hours and minutes, e.g. 1h 30m
+    #[default]
+    Text,
+    /// A clock, e.g. 1:30
+    Clock,
+    /// Decimal hours, e.g. 1.50
+    Decimal,
+}
+
+static DURATION_FORMAT: OnceLock<DurationFormat> = OnceLock::new();
+
+pub(crate) fn set_duration_format(format: DurationFormat) {
+    DURATION_FORMAT.set(format).unwrap();
+}
+
+/// Parses an ISO-8601 duration such as `PT1H30M` or `P1DT2H`
+pub(crate) fn parse_duration(duration: &str) -> Option<Duration> {
+    let duration = duration.trim().parse::<IsoDuration>().ok()?;
+    Duration::from_std(duration.to_std()?).ok()
+}
+
+/// Formats a duration using the configured [`DurationFormat`]
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let format = DURATION_FORMAT.get().copied().unwrap_or_default();
+    let minutes = (duration.num_seconds() + 30) / 60;
+    match format {
+        DurationFormat::Text if minutes == 0 && duration.num_seconds() > 0 => {
+            format!("{}s", duration.num_seconds())
+        }
+        DurationFormat::Text if minutes < 60 => format!("{}m", minutes),
+        DurationFormat::Text => format!("{}h {}m", minutes / 60, minutes % 60),
+        DurationFormat::Clock => format!("{}:{:02}", minutes / 60, minutes % 60),
+        DurationFormat::Decimal => format!("{:.2}", duration.num_seconds() as f64 / 3600.0),
+    }
+}
+
+/// Rounds a duration to the nearest multiple of `minutes`, leaving it as is for 0
+pub(crate) fn round_duration(duration: Duration, minutes: u32) -> Duration {
+    if minutes == 0 {
+        return duration;
+    }
+    let step = minutes as i64 * 60;
+    let seconds = (duration.num_seconds() + step / 2) / step * step;
+    Duration::seconds(seconds)
+}
+
+/// Formats an ISO-8601 duration as received from Clockify, which is missing
+/// while the task is still running
+pub(crate) fn display_duration(duration: Option<&str>) -> String {
+    let duration = match duration {
+        Some(duration) if !duration.is_empty() => duration,
+        _ => return String::from("In progress"),
+    };
+    match parse_duration(duration) {
+        Some(duration) if duration.is_zero() => String::from("No time registered"),
+        Some(duration) => format_duration(duration),
+        None => format!("Unknown duration {}", duration),
+    }
+}
+
+/// Parses a span of time such as `30m`, `1h30m`, `1h30`, `1h` or `45` (minutes).
+/// Negative spans and spans too long to be represented are rejected.
+pub(crate) fn parse_span(span: &str) -> Option<Duration> {
+    let span = span.trim().to_lowercase();
+    if span.chars().all(|c| c.is_ascii_digit()) {
+        return Duration::try_minutes(span.parse::<i64>().ok()?);
+    }
+    let regex = Regex::new(r"^(?:(\d+)h)?(?:(\d+)m?)?$").unwrap();
+    let captures = regex.captures(&span)?;
+    if captures.get(1).is_none() && captures.get(2).is_none() {
+        return None;
+    }
+    let hours = match captures.get(1) {
+        Some(hours) => hours.as_str().parse::<i64>().ok()?,
+        None => 0,
+    };
+    let minutes = match captures.get(2) {
+        Some(minutes) => minutes.as_str().parse::<i64>().ok()?,
+        None => 0,
+    };
+    Duration::try_minutes(hours.checked_mul(60)?.checked_add(minutes)?)
+}
+
+/// Returns the names of the `{placeholders}` used in the text
+pub(crate) fn placeholders(text: &str) -> Vec<String> {
+    PLACEHOLDER
+        .captures_iter(text)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+/// Replaces the `{placeholders}` of the text, unknown ones are left untouched
+pub(crate) fn expand(text: &str, vars: &HashMap<String, String>) -> String {
+    PLACEHOLDER
+        .replace_all(text, |captures: &regex::Captures| {
+            vars.get(&captures[1])
+                .cloned()
+                .unwrap_or_else(|| captures[0].to_string())
+        })
+        .to_string()
+}
+
+/// Returns the branch checked out in the current directory, if it is a git repository
+pub(crate) fn git_branch() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let branch = String::from_utf8(output.stdout).ok()?;
+    Some(branch.trim().to_string())
+}
+
+/// Parses an end time typed as a duration, such as `1h30m`, `1h30` or `45m`
+pub(crate) fn parse_end_span(input: &str) -> Option<Duration> {
+    let input = input.trim().to_lowercase();
+    if !input.contains('h') && !input.ends_with('m') {
+        return None;
+    }
+    parse_span(&input).filter(|span| *span < Duration::days(1))
+}
+
+/// Parses a time typed by the user, accepting `9`, `930`, `9:30`, `9:30am`, `now`
+/// and offsets from now such as `-15m` or `+1h`. When `start` is given the time
+/// is an end time, so durations such as `1h30m` or `1h30` are added to it.
+pub(crate) fn parse_time(input: &str, start: Option<NaiveTime>) -> Option<NaiveTime> {
+    let input = input.trim().to_lowercase();
+    let current = now().time().with_second(0)?.with_nanosecond(0)?;
+    if input == "now" {
+        return Some(current);
+    }
+    // Offsets that would cross midnight are rejected, as they would land on another date
+    if let Some(offset) = input.strip_prefix('-') {
+        let (time, wrapped) = current.overflowing_sub_signed(parse_span(offset)?);
+        return (wrapped == 0).then_some(time);
+    }
+    if let Some(offset) = input.strip_prefix('+') {
+        let (time, wrapped) = current.overflowing_add_signed(parse_span(offset)?);
+        return (wrapped == 0).then_some(time);
+    }
+    if let Some(start) = start {
+        if let Some(span) = parse_end_span(&input) {
+            return Some(start + span);
+        }
+    }
+
+    let captures = TIME.captures(&input)?;
+    let (mut hour, minute) = match captures.get(2) {
+        Some(minute) => (
+            captures[1].parse::<u32>().ok()?,
+            minute.as_str().parse::<u32>().ok()?,
+        ),
+        None => {
+            let digits = &captures[1];
+            match digits.len() {
+                1 | 2 => (digits.parse::<u32>().ok()?, 0),
+                3 | 4 => {
+                    let (hour, minute) = digits.split_at(digits.len() - 2);
+                    (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?)
+                }
+                _ => return None,
             }
+        }
+    };
+    match captures.get(3).map(|meridiem| &meridiem.as_str()[..1]) {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some("a") if hour == 12 => hour = 0,
+        Some("p") if hour != 12 => hour += 12,
+        _ => {}
+    }
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// Parses a weekday name, which can be shortened down to its first three letters
+pub(crate) fn parse_weekday(input: &str) -> Option<Weekday> {
+    let input = input.to_ascii_lowercase();
+    if input.len() < 3 {
+        return None;
+    }
+    let weekdays = [
+        ("monday", Weekday::Mon),
+        ("tuesday", Weekday::Tue),
+        ("wednesday", Weekday::Wed),
+        ("thursday", Weekday::Thu),
+        ("friday", Weekday::Fri),
+        ("saturday", Weekday::Sat),
+        ("sunday", Weekday::Sun),
+    ];
+    weekdays
+        .iter()
+        .find(|(name, _)| name.starts_with(input.as_str()))
+        .map(|(_, weekday)| *weekday)
+}
+
+/// The first day of the week containing `date`, for weeks starting on `first`
+pub(crate) fn week_start(date: NaiveDate, first: Weekday) -> NaiveDate {
+    let days = (date.weekday().num_days_from_monday() + 7 - first.num_days_from_monday()) % 7;
+    date - Duration::days(days as i64)
+}
+
+/// A span of time, from its start to its end
+pub(crate) type Interval = (DateTime<Utc>, DateTime<Utc>);
+
+/// Splits the interval at local midnight, returning how long it lasts on each day
+pub(crate) fn split_days(interval: Interval) -> Vec<(NaiveDate, Duration)> {
+    let mut days = Vec::new();
+    let mut date = to_local(interval.0).date();
+    while datetime(date, NaiveTime::MIN) < interval.1 {
+        let next = date + Duration::days(1);
+        let from = datetime(date, NaiveTime::MIN).max(interval.0);
+        let until = datetime(next, NaiveTime::MIN).min(interval.1);
+        days.push((date, until - from));
+        date = next;
+    }
+    days
+}
+
+/// Removes `busy` from each of the `free` intervals
+pub(crate) fn subtract(free: Vec<Interval>, busy: Interval) -> Vec<Interval> {
+    let mut left = Vec::new();
+    for (start, end) in free {
+        if busy.1 <= start || busy.0 >= end {
+            left.push((start, end));
+            continue;
+        }
+        if busy.0 > start {
+            left.push((start, busy.0));
+        }
+        if busy.1 < end {
+            left.push((busy.1, end));
+        }
+    }
+    left
+}
+
+/// Parses an ISO week such as `2023-W14`, or any date accepted by [`parse_date`],
+/// and returns the first day of that week
+pub(crate) fn parse_week(input: &str, first: Weekday) -> Option<NaiveDate> {
+    let date = match ISO_WEEK.captures(input.trim()) {
+        Some(iso) => {
+            NaiveDate::from_isoywd_opt(iso[1].parse().ok()?, iso[2].parse().ok()?, Weekday::Mon)?
+        }
+        None => parse_date(input)?,
+    };
+    Some(week_start(date, first))
+}
 
-            output.push_str(&format!(
-                "{} {}",
-                time,
-                TIME_NAMES.get(&matches[0].1).unwrap()
-            ));
+/// Parses a date typed by the user: `today`, `yesterday`, `tomorrow`, weekdays
+/// such as `mon` (the last one, today included), `last friday`, `next monday`,
+/// offsets such as `-3d` or `+1w` and ISO dates such as `2023-04-03`
+pub(crate) fn parse_date(input: &str) -> Option<NaiveDate> {
+    let input = input.trim().to_lowercase();
+    let today = now().date();
+    match input.as_str() {
+        "today" => return Some(today),
+        "yesterday" => return Some(today - Duration::days(1)),
+        "tomorrow" => return Some(today + Duration::days(1)),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+        return Some(date);
+    }
+    if let Some(captures) = DATE_OFFSET.captures(&input) {
+        let amount = captures[2].parse::<i64>().ok()?;
+        let days = match &captures[3] {
+            "w" => amount.checked_mul(7)?,
+            _ => amount,
+        };
+        let offset = Duration::try_days(days)?;
+        return match &captures[1] {
+            "-" => today.checked_sub_signed(offset),
+            _ => today.checked_add_signed(offset),
+        };
+    }
+
+    let (direction, weekday) = match input.split_once(' ') {
+        Some(("last", weekday)) => ("last", parse_weekday(weekday.trim())?),
+        Some(("next", weekday)) => ("next", parse_weekday(weekday.trim())?),
+        Some(_) => return None,
+        None => ("", parse_weekday(&input)?),
+    };
+    let current = today.weekday().num_days_from_monday() as i64;
+    let target = weekday.num_days_from_monday() as i64;
+    let date = match direction {
+        "next" => {
+            let days = (target - current).rem_euclid(7);
+            today + Duration::days(if days == 0 { 7 } else { days })
         }
-        2 => output.push_str(&format!(
-            "{} {} & {} {}",
-            process[0],
-            TIME_NAMES.get(&matches[0].1).unwrap(),
-            process[1],
-            TIME_NAMES.get(&matches[1].1).unwrap()
-        )),
-        3 => output.push_str(&format!(
-            "{} {}, {} {} & {} {}",
-            process[0],
-            TIME_NAMES.get(&matches[0].1).unwrap(),
-            process[1],
-            TIME_NAMES.get(&matches[1].1).unwrap(),
-            process[2],
-            TIME_NAMES.get(&matches[2].1).unwrap()
-        )),
-        _ => output.push_str("No time registered"),
-    }
-    output
+        "last" => {
+            let days = (current - target).rem_euclid(7);
+            today - Duration::days(if days == 0 { 7 } else { days })
+        }
+        _ => today - Duration::days((current - target).rem_euclid(7)),
+    };
+    Some(date)
+}
+
+/// Matches the options of a picker that contain the typed characters in order,
+/// ignoring case, so `prx` matches `Project X`
+pub(crate) fn fuzzy_filter(input: &str, _: &String, value: &str, _: usize) -> bool {
+    let mut chars = value.chars().flat_map(char::to_lowercase);
+    input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|c| chars.any(|other| other == c))
+}
+
+static CLEAR: OnceLock<bool> = OnceLock::new();
+static COLOR: OnceLock<bool> = OnceLock::new();
+static INTERACTIVE: OnceLock<bool> = OnceLock::new();
+
+/// Sets whether the screen may be cleared between prompts
+pub fn set_clear(clear: bool) {
+    CLEAR.set(clear).unwrap();
+}
+
+/// Sets whether output may be colored
+pub fn set_color(color: bool) {
+    COLOR.set(color).unwrap();
+}
+
+/// Wraps the text in an ANSI color code, only when writing to a terminal that allows colors
+pub(crate) fn paint(text: &str, code: &str) -> String {
+    if !io::stdout().is_terminal() || !COLOR.get().copied().unwrap_or(true) {
+        return text.to_string();
+    }
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+/// Whether both stdin and stdout are a terminal
+pub fn terminal() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Sets whether prompts can be answered, either in a terminal or from an answers file
+pub fn set_interactive(interactive: bool) {
+    INTERACTIVE.set(interactive).unwrap();
+}
+
+/// Whether prompts can be answered
+pub fn interactive() -> bool {
+    *INTERACTIVE.get_or_init(terminal)
+}
+
+/// Exits with an error naming the flag to use when `input` would have to be prompted for
+/// without a terminal or an answers file
+pub(crate) fn require_input(input: &str, hint: &str) {
+    if interactive() {
+        return;
+    }
+    eprintln!("Cannot ask for {} without a terminal, {}", input, hint);
+    process::exit(2);
 }
 
 pub(crate) fn clear_screen() {
+    if !terminal() || !CLEAR.get().copied().unwrap_or(true) {
+        return;
+    }
     let mut stdout = io::stdout();
     write!(stdout, "{esc}c", esc = 27 as char).unwrap();
     stdout.flush().unwrap()
 }
 
-pub(crate) fn cursor() {
-    let mut stdout = io::stdout();
-    write!(stdout, "> ").unwrap();
-    stdout.flush().unwrap()
+/// The timezone used to read and show the times of the tasks
+#[derive(Clone, Copy, Debug)]
+pub enum Zone {
+    Local,
+    Named(Tz),
 }
 
-pub(crate) fn read<T>() -> Option<T>
-where
-    T: std::str::FromStr,
-{
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input).unwrap();
-    input.trim().parse::<T>().ok()
+static ZONE: OnceLock<Zone> = OnceLock::new();
+
+impl Zone {
+    /// Parses an IANA timezone name such as `Europe/Madrid`, or `local` for the system timezone
+    pub fn parse(name: &str) -> Option<Zone> {
+        if name.eq_ignore_ascii_case("local") {
+            return Some(Zone::Local);
+        }
+        name.parse::<Tz>().ok().map(Zone::Named)
+    }
 }
 
-pub(crate) fn date(time: NaiveTime) -> DateTime<Utc> {
-    let now = Utc::now();
-    let local = Local.timestamp_opt(0, 0).unwrap().offset().fix();
-    let date = NaiveDateTime::new(now.date_naive(), time);
-    let date = local.from_local_datetime(&date).unwrap();
-    date.with_timezone(&Utc)
+pub(crate) fn set_zone(zone: Zone) {
+    ZONE.set(zone).unwrap();
+}
+
+pub(crate) fn zone() -> Zone {
+    ZONE.get().copied().unwrap_or(Zone::Local)
+}
+
+fn from_local<T: TimeZone>(tz: &T, date: NaiveDateTime) -> DateTime<Utc> {
+    match tz.from_local_datetime(&date) {
+        LocalResult::Single(date) => date.with_timezone(&Utc),
+        LocalResult::Ambiguous(first, second) => {
+            println!(
+                "{} happens twice in this timezone, using the first one ({} instead of {})",
+                date.format("%Y-%m-%d %H:%M"),
+                first.offset().fix(),
+                second.offset().fix()
+            );
+            first.with_timezone(&Utc)
+        }
+        LocalResult::None => {
+            // The clock skips this time (e.g. when DST starts), so it is read
+            // with the offset from before the gap, moving it forward.
+            let before = tz
+                .from_local_datetime(&(date - Duration::hours(3)))
+                .earliest()
+                .unwrap()
+                .offset()
+                .fix();
+            let shifted = before.from_local_datetime(&date).unwrap();
+            println!(
+                "{} does not exist in this timezone, using {}",
+                date.format("%Y-%m-%d %H:%M"),
+                tz.from_utc_datetime(&shifted.naive_utc())
+                    .naive_local()
+                    .format("%Y-%m-%d %H:%M")
+            );
+            shifted.with_timezone(&Utc)
+        }
+    }
+}
+
+/// Converts a local date and time in the configured timezone to UTC
+pub(crate) fn to_utc(date: NaiveDateTime) -> DateTime<Utc> {
+    match zone() {
+        Zone::Local => from_local(&Local, date),
+        Zone::Named(tz) => from_local(&tz, date),
+    }
+}
+
+/// Converts a UTC timestamp to the local date and time of the configured timezone
+pub(crate) fn to_local(date: DateTime<Utc>) -> NaiveDateTime {
+    match zone() {
+        Zone::Local => date.with_timezone(&Local).naive_local(),
+        Zone::Named(tz) => date.with_timezone(&tz).naive_local(),
+    }
+}
+
+/// The current date and time in the configured timezone
+pub(crate) fn now() -> NaiveDateTime {
+    to_local(Utc::now())
 }
 
 pub(crate) fn datetime(date: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
-    let local = Local.timestamp_opt(0, 0).unwrap().offset().fix();
-    let date = NaiveDateTime::new(date, time);
-    let date = local.from_local_datetime(&date).unwrap();
-    date.with_timezone(&Utc)
+    to_utc(NaiveDateTime::new(date, time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_spans() {
+        assert_eq!(parse_span("45"), Some(Duration::minutes(45)));
+        assert_eq!(parse_span("1h30m"), Some(Duration::minutes(90)));
+        assert_eq!(parse_span("1h30"), Some(Duration::minutes(90)));
+        assert_eq!(parse_span("2h"), Some(Duration::hours(2)));
+        assert_eq!(parse_span("-30"), None);
+        assert_eq!(parse_span("99999999999999999999"), None);
+        assert_eq!(parse_span("9999999999999999h"), None);
+        assert_eq!(parse_span(""), None);
+    }
+
+    #[test]
+    fn parses_end_times_as_durations() {
+        let start = Some(time(9, 0));
+        assert_eq!(parse_time("1h30", start), Some(time(10, 30)));
+        assert_eq!(parse_time("45m", start), Some(time(9, 45)));
+        assert_eq!(parse_time("17:30", start), Some(time(17, 30)));
+        assert_eq!(parse_time("24h", start), None);
+        assert_eq!(parse_time("9h30", None), Some(time(9, 30)));
+    }
+
+    #[test]
+    fn rejects_date_offsets_out_of_range() {
+        let today = now().date();
+        assert_eq!(parse_date("-3d"), Some(today - Duration::days(3)));
+        assert_eq!(parse_date("+1w"), Some(today + Duration::weeks(1)));
+        assert_eq!(parse_date("-100000000d"), None);
+        assert_eq!(parse_date("+9999999999999999999w"), None);
+    }
+
+    #[test]
+    fn parses_clock_times() {
+        assert_eq!(parse_time("930", None), Some(time(9, 30)));
+        assert_eq!(parse_time("9:30pm", None), Some(time(21, 30)));
+        assert_eq!(parse_time("12am", None), Some(time(0, 0)));
+        assert_eq!(parse_time("25:00", None), None);
+    }
+
+    #[test]
+    fn parses_weekdays_in_any_case() {
+        assert_eq!(parse_weekday("Sunday"), Some(Weekday::Sun));
+        assert_eq!(parse_weekday("MON"), Some(Weekday::Mon));
+        assert_eq!(parse_weekday("tu"), None);
+    }
+
+    #[test]
+    fn splits_intervals_at_midnight() {
+        let day = NaiveDate::from_ymd_opt(2023, 1, 10).unwrap();
+        let next = day + Duration::days(1);
+        let interval = (datetime(day, time(22, 0)), datetime(next, time(6, 0)));
+        assert_eq!(
+            split_days(interval),
+            vec![(day, Duration::hours(2)), (next, Duration::hours(6))]
+        );
+        let interval = (datetime(day, time(9, 0)), datetime(day, time(17, 0)));
+        assert_eq!(split_days(interval), vec![(day, Duration::hours(8))]);
+    }
 }